@@ -0,0 +1,18 @@
+use std::env;
+use std::path::PathBuf;
+
+// The header is generated into OUT_DIR only, tests/header.rs checks that the committed copy in include/ matches it
+fn main() {
+    let dir = PathBuf::from(env::var("CARGO_MANIFEST_DIR").unwrap());
+    let config = cbindgen::Config::from_file(dir.join("cbindgen.toml")).unwrap();
+
+    cbindgen::Builder::new()
+        .with_crate(&dir)
+        .with_config(config)
+        .generate()
+        .expect("Unable to generate C header")
+        .write_to_file(PathBuf::from(env::var("OUT_DIR").unwrap()).join("chip8.h"));
+
+    println!("cargo:rerun-if-changed=src/lib.rs");
+    println!("cargo:rerun-if-changed=cbindgen.toml");
+}
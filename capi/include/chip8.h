@@ -0,0 +1,144 @@
+#ifndef CHIP8_H
+#define CHIP8_H
+
+/* Generated by cbindgen from capi/src/lib.rs, do not edit */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+/**
+ * Result of fallible calls, the message of the last error is available from `chip8_get_error`.
+ */
+typedef enum Chip8Status {
+  CHIP8_STATUS_OK = 0,
+  CHIP8_STATUS_NULL_POINTER,
+  CHIP8_STATUS_BUFFER_TOO_SMALL,
+  CHIP8_STATUS_ERROR,
+} Chip8Status;
+
+/**
+ * Opaque machine handle, created by `chip8_new` and released by `chip8_free`.
+ */
+typedef struct Chip8Machine Chip8Machine;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+/**
+ * Creates a machine with the default CPU frequency, free it with `chip8_free`.
+ */
+struct Chip8Machine *chip8_new(void);
+
+/**
+ * # Safety
+ *
+ * `machine` must be null or a handle from `chip8_new` not freed yet.
+ */
+void chip8_free(struct Chip8Machine *machine);
+
+/**
+ * Message of the last error, valid until the next failing call on the same machine.
+ *
+ * # Safety
+ *
+ * `machine` must be null or a valid handle.
+ */
+const char *chip8_get_error(const struct Chip8Machine *machine);
+
+/**
+ * Resets the machine, so that nothing from a previous ROM is left in memory, on screen or in the keypad.
+ *
+ * # Safety
+ *
+ * `machine` must be a valid handle, and `rom` must point to `len` readable bytes.
+ */
+enum Chip8Status chip8_load_rom(struct Chip8Machine *machine,
+                                const uint8_t *rom,
+                                size_t len);
+
+/**
+ * Runs whole frames, each made of the instructions of one timer tick followed by the tick.
+ *
+ * # Safety
+ *
+ * `machine` must be a valid handle.
+ */
+enum Chip8Status chip8_step_frames(struct Chip8Machine *machine, uint32_t frames);
+
+/**
+ * Keys are numbered 0x0 to 0xf, as on the original keypad.
+ *
+ * # Safety
+ *
+ * `machine` must be a valid handle.
+ */
+enum Chip8Status chip8_set_key(struct Chip8Machine *machine, uint8_t key, bool down);
+
+/**
+ * # Safety
+ *
+ * `machine` must be a valid handle.
+ */
+size_t chip8_get_width(const struct Chip8Machine *machine);
+
+/**
+ * # Safety
+ *
+ * `machine` must be a valid handle.
+ */
+size_t chip8_get_height(const struct Chip8Machine *machine);
+
+/**
+ * Writes one byte per pixel, row major, 1 for lit pixels and 0 otherwise.
+ *
+ * # Safety
+ *
+ * `machine` must be a valid handle, and `out` must point to `len` writable bytes.
+ */
+enum Chip8Status chip8_get_framebuffer(const struct Chip8Machine *machine,
+                                       uint8_t *out,
+                                       size_t len);
+
+/**
+ * Whether the buzzer sounded at the end of the last frame.
+ *
+ * # Safety
+ *
+ * `machine` must be a valid handle.
+ */
+bool chip8_is_beeping(const struct Chip8Machine *machine);
+
+/**
+ * Size of the buffers used by `chip8_save_state` and `chip8_load_state`.
+ */
+size_t chip8_get_state_size(void);
+
+/**
+ * Writes the first `chip8_get_state_size()` bytes of `out`.
+ *
+ * # Safety
+ *
+ * `machine` must be a valid handle, and `out` must point to `len` writable bytes.
+ */
+enum Chip8Status chip8_save_state(struct Chip8Machine *machine, uint8_t *out, size_t len);
+
+/**
+ * Reads the first `chip8_get_state_size()` bytes of `state`, the machine is left untouched on errors.
+ *
+ * # Safety
+ *
+ * `machine` must be a valid handle, and `state` must point to `len` readable bytes.
+ */
+enum Chip8Status chip8_load_state(struct Chip8Machine *machine,
+                                  const uint8_t *state,
+                                  size_t len);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* CHIP8_H */
@@ -0,0 +1,257 @@
+use std::ffi::{c_char, CString};
+use std::ptr;
+
+use chip8::{Chip8, Framebuffer, Screen, IO, STATE_SIZE};
+
+/// Opaque machine handle, created by `chip8_new` and released by `chip8_free`.
+pub struct Chip8Machine {
+    chip8: Chip8,
+    screen: Framebuffer,
+    pad: [bool; 0x10],
+    beep: bool,
+    error: CString,
+}
+
+/// Result of fallible calls, the message of the last error is available from `chip8_get_error`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Chip8Status {
+    Ok = 0,
+    NullPointer,
+    BufferTooSmall,
+    Error,
+}
+
+impl Chip8Machine {
+    fn new() -> Self {
+        let chip8 = Chip8::new(None);
+        let (width, height) = chip8.get_screen_size();
+
+        Self {
+            chip8,
+            screen: Framebuffer::new(width, height).unwrap(),
+            pad: [false; 0x10],
+            beep: false,
+            error: CString::default(),
+        }
+    }
+
+    fn status(&mut self, result: Result<(), chip8::Error>) -> Chip8Status {
+        match result {
+            Ok(()) => Chip8Status::Ok,
+            Err(err) => {
+                self.error = CString::new(err.to_string()).unwrap_or_default();
+                Chip8Status::Error
+            }
+        }
+    }
+
+    // The machine is only replaced once the ROM loaded
+    fn load_rom(&mut self, rom: &[u8]) -> Result<(), chip8::Error> {
+        let mut chip8 = Chip8::new(None);
+        chip8.load_rom(rom, None)?;
+
+        self.chip8 = chip8;
+        self.screen.clear();
+        self.pad = [false; 0x10];
+        self.beep = false;
+
+        Ok(())
+    }
+
+    fn run_frames(&mut self, frames: u32) -> Result<(), chip8::Error> {
+        for _ in 0..frames {
+            self.chip8.frame(&mut IO {
+                screen: &mut self.screen,
+                pad: &self.pad,
+                audio: &mut self.beep,
+            })?;
+        }
+
+        Ok(())
+    }
+}
+
+/// Creates a machine with the default CPU frequency, free it with `chip8_free`.
+#[no_mangle]
+pub extern "C" fn chip8_new() -> *mut Chip8Machine {
+    Box::into_raw(Box::new(Chip8Machine::new()))
+}
+
+/// # Safety
+///
+/// `machine` must be null or a handle from `chip8_new` not freed yet.
+#[no_mangle]
+pub unsafe extern "C" fn chip8_free(machine: *mut Chip8Machine) {
+    if !machine.is_null() {
+        drop(Box::from_raw(machine));
+    }
+}
+
+/// Message of the last error, valid until the next failing call on the same machine.
+///
+/// # Safety
+///
+/// `machine` must be null or a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn chip8_get_error(machine: *const Chip8Machine) -> *const c_char {
+    match machine.as_ref() {
+        Some(machine) => machine.error.as_ptr(),
+        None => ptr::null(),
+    }
+}
+
+/// Resets the machine, so that nothing from a previous ROM is left in memory, on screen or in the keypad.
+///
+/// # Safety
+///
+/// `machine` must be a valid handle, and `rom` must point to `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn chip8_load_rom(machine: *mut Chip8Machine, rom: *const u8, len: usize) -> Chip8Status {
+    match (machine.as_mut(), rom.is_null()) {
+        (Some(machine), false) => {
+            let rom = std::slice::from_raw_parts(rom, len);
+            let result = machine.load_rom(rom);
+            machine.status(result)
+        }
+        _ => Chip8Status::NullPointer,
+    }
+}
+
+/// Runs whole frames, each made of the instructions of one timer tick followed by the tick.
+///
+/// # Safety
+///
+/// `machine` must be a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn chip8_step_frames(machine: *mut Chip8Machine, frames: u32) -> Chip8Status {
+    match machine.as_mut() {
+        Some(machine) => {
+            let result = machine.run_frames(frames);
+            machine.status(result)
+        }
+        None => Chip8Status::NullPointer,
+    }
+}
+
+/// Keys are numbered 0x0 to 0xf, as on the original keypad.
+///
+/// # Safety
+///
+/// `machine` must be a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn chip8_set_key(machine: *mut Chip8Machine, key: u8, down: bool) -> Chip8Status {
+    match machine.as_mut() {
+        Some(machine) => match machine.pad.get_mut(key as usize) {
+            Some(pad) => {
+                *pad = down;
+                Chip8Status::Ok
+            }
+            None => machine.status(Err(chip8::Error::PadOutOfRange(key))),
+        },
+        None => Chip8Status::NullPointer,
+    }
+}
+
+/// # Safety
+///
+/// `machine` must be a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn chip8_get_width(machine: *const Chip8Machine) -> usize {
+    machine
+        .as_ref()
+        .map(|machine| machine.screen.get_width())
+        .unwrap_or_default()
+}
+
+/// # Safety
+///
+/// `machine` must be a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn chip8_get_height(machine: *const Chip8Machine) -> usize {
+    machine
+        .as_ref()
+        .map(|machine| machine.screen.get_height())
+        .unwrap_or_default()
+}
+
+/// Writes one byte per pixel, row major, 1 for lit pixels and 0 otherwise.
+///
+/// # Safety
+///
+/// `machine` must be a valid handle, and `out` must point to `len` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn chip8_get_framebuffer(machine: *const Chip8Machine, out: *mut u8, len: usize) -> Chip8Status {
+    let machine = match (machine.as_ref(), out.is_null()) {
+        (Some(machine), false) => machine,
+        _ => return Chip8Status::NullPointer,
+    };
+
+    let (width, height) = machine.screen.size();
+
+    if len < width * height {
+        return Chip8Status::BufferTooSmall;
+    }
+
+    let out = std::slice::from_raw_parts_mut(out, len);
+
+    for (row, line) in machine.screen.get_rows().iter().zip(out.chunks_mut(width)) {
+        for (x, px) in line.iter_mut().enumerate() {
+            *px = ((row >> x) & 1) as u8;
+        }
+    }
+
+    Chip8Status::Ok
+}
+
+/// Whether the buzzer sounded at the end of the last frame.
+///
+/// # Safety
+///
+/// `machine` must be a valid handle.
+#[no_mangle]
+pub unsafe extern "C" fn chip8_is_beeping(machine: *const Chip8Machine) -> bool {
+    machine.as_ref().is_some_and(|machine| machine.beep)
+}
+
+/// Size of the buffers used by `chip8_save_state` and `chip8_load_state`.
+#[no_mangle]
+pub extern "C" fn chip8_get_state_size() -> usize {
+    STATE_SIZE
+}
+
+/// Writes the first `chip8_get_state_size()` bytes of `out`.
+///
+/// # Safety
+///
+/// `machine` must be a valid handle, and `out` must point to `len` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn chip8_save_state(machine: *mut Chip8Machine, out: *mut u8, len: usize) -> Chip8Status {
+    match (machine.as_mut(), out.is_null()) {
+        (Some(_), false) if len < STATE_SIZE => Chip8Status::BufferTooSmall,
+        (Some(machine), false) => {
+            let out = std::slice::from_raw_parts_mut(out, STATE_SIZE);
+            let result = machine.chip8.save_state(&machine.screen, out);
+            machine.status(result)
+        }
+        _ => Chip8Status::NullPointer,
+    }
+}
+
+/// Reads the first `chip8_get_state_size()` bytes of `state`, the machine is left untouched on errors.
+///
+/// # Safety
+///
+/// `machine` must be a valid handle, and `state` must point to `len` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn chip8_load_state(machine: *mut Chip8Machine, state: *const u8, len: usize) -> Chip8Status {
+    match (machine.as_mut(), state.is_null()) {
+        (Some(_), false) if len < STATE_SIZE => Chip8Status::BufferTooSmall,
+        (Some(machine), false) => {
+            let state = std::slice::from_raw_parts(state, STATE_SIZE);
+            let result = machine.chip8.load_state(&mut machine.screen, state);
+            machine.status(result)
+        }
+        _ => Chip8Status::NullPointer,
+    }
+}
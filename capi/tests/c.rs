@@ -0,0 +1,41 @@
+use std::path::Path;
+use std::process::Command;
+
+// Builds the C test program against the generated header and the shared library, then runs it
+#[test]
+fn c_program() {
+    let manifest = Path::new(env!("CARGO_MANIFEST_DIR"));
+    let lib_dir = std::env::current_exe()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .parent()
+        .unwrap()
+        .to_owned();
+    let exe = lib_dir.join("chip8_capi_test");
+
+    let status = Command::new(std::env::var("CC").unwrap_or_else(|_| String::from("cc")))
+        .arg("-std=c99")
+        .arg("-Wall")
+        .arg("-Werror")
+        .arg("-I")
+        .arg(manifest.join("include"))
+        .arg(manifest.join("tests").join("c").join("test.c"))
+        .arg("-L")
+        .arg(&lib_dir)
+        .arg("-lchip8_capi")
+        .arg("-o")
+        .arg(&exe)
+        .status()
+        .unwrap();
+
+    assert!(status.success());
+
+    let status = Command::new(&exe)
+        .env("LD_LIBRARY_PATH", &lib_dir)
+        .env("DYLD_LIBRARY_PATH", &lib_dir)
+        .status()
+        .unwrap();
+
+    assert!(status.success());
+}
@@ -0,0 +1,83 @@
+#include <stdio.h>
+#include <string.h>
+
+#include "chip8.h"
+
+#define CHECK(cond)                                                                   \
+    do {                                                                              \
+        if (!(cond)) {                                                                \
+            fprintf(stderr, "%s:%d: check failed: %s\n", __FILE__, __LINE__, #cond); \
+            return 1;                                                                 \
+        }                                                                             \
+    } while (0)
+
+/* Waits for a key, draws its glyph in the top left corner and sounds the buzzer for 5 ticks */
+static const uint8_t ROM[] = {
+    0xf0, 0x0a, 0xf0, 0x29, 0xd1, 0x25, 0x60, 0x05, 0xf0, 0x18, 0x12, 0x0a,
+};
+
+static int pixel(Chip8Machine *machine, size_t x, size_t y) {
+    uint8_t pixels[64 * 32];
+
+    if (chip8_get_framebuffer(machine, pixels, sizeof(pixels)) != CHIP8_STATUS_OK) {
+        return -1;
+    }
+
+    return pixels[y * chip8_get_width(machine) + x];
+}
+
+int main(void) {
+    Chip8Machine *machine = chip8_new();
+    uint8_t small[16];
+    uint8_t state[8192];
+    size_t size = chip8_get_state_size();
+
+    CHECK(machine != NULL);
+    CHECK(size <= sizeof(state));
+    CHECK(chip8_get_width(machine) == 64 && chip8_get_height(machine) == 32);
+    CHECK(chip8_load_rom(machine, ROM, sizeof(ROM)) == CHIP8_STATUS_OK);
+
+    CHECK(chip8_step_frames(machine, 2) == CHIP8_STATUS_OK);
+    CHECK(pixel(machine, 0, 0) == 0);
+    CHECK(!chip8_is_beeping(machine));
+
+    /* Glyph 5 is 0xf0, 0x80, 0xf0, 0x10, 0xf0 */
+    CHECK(chip8_set_key(machine, 0x5, true) == CHIP8_STATUS_OK);
+    CHECK(chip8_step_frames(machine, 1) == CHIP8_STATUS_OK);
+    CHECK(pixel(machine, 0, 1) == 1 && pixel(machine, 3, 1) == 0);
+    CHECK(pixel(machine, 0, 3) == 0 && pixel(machine, 3, 3) == 1);
+    CHECK(chip8_is_beeping(machine));
+
+    CHECK(chip8_save_state(machine, state, size) == CHIP8_STATUS_OK);
+    CHECK(chip8_step_frames(machine, 10) == CHIP8_STATUS_OK);
+    CHECK(!chip8_is_beeping(machine));
+
+    CHECK(chip8_load_state(machine, state, size) == CHIP8_STATUS_OK);
+    CHECK(chip8_step_frames(machine, 1) == CHIP8_STATUS_OK);
+    CHECK(chip8_is_beeping(machine));
+    CHECK(pixel(machine, 3, 3) == 1);
+
+    /* Errors are reported with a message */
+    CHECK(chip8_set_key(machine, 0x10, true) == CHIP8_STATUS_ERROR);
+    CHECK(strcmp(chip8_get_error(machine), "Pad key 0x10 is invalid") == 0);
+    state[0] ^= 0xff;
+    CHECK(chip8_load_state(machine, state, size) == CHIP8_STATUS_ERROR);
+    CHECK(strlen(chip8_get_error(machine)) > 0);
+    CHECK(chip8_step_frames(NULL, 1) == CHIP8_STATUS_NULL_POINTER);
+
+    /* Short buffers are rejected before anything is read or written */
+    CHECK(chip8_save_state(machine, state, size - 1) == CHIP8_STATUS_BUFFER_TOO_SMALL);
+    CHECK(chip8_load_state(machine, state, size - 1) == CHIP8_STATUS_BUFFER_TOO_SMALL);
+    CHECK(chip8_get_framebuffer(machine, small, sizeof(small)) == CHIP8_STATUS_BUFFER_TOO_SMALL);
+
+    /* Loading a ROM starts over on a blank machine */
+    CHECK(chip8_load_rom(machine, ROM, sizeof(ROM)) == CHIP8_STATUS_OK);
+    CHECK(pixel(machine, 0, 1) == 0 && pixel(machine, 3, 3) == 0);
+    CHECK(!chip8_is_beeping(machine));
+    CHECK(chip8_step_frames(machine, 2) == CHIP8_STATUS_OK);
+    CHECK(pixel(machine, 0, 1) == 0);
+
+    chip8_free(machine);
+
+    return 0;
+}
@@ -0,0 +1,24 @@
+use std::path::Path;
+
+// Generated by build.rs from the current sources
+const GENERATED: &str = include_str!(concat!(env!("OUT_DIR"), "/chip8.h"));
+
+// The committed header is what C users build against, set CHIP8_UPDATE_HEADER=1 to regenerate it
+#[test]
+fn header_is_up_to_date() {
+    let header = Path::new(env!("CARGO_MANIFEST_DIR")).join("include").join("chip8.h");
+    let committed = std::fs::read_to_string(&header).unwrap_or_default();
+
+    if committed == GENERATED {
+        return;
+    }
+
+    if std::env::var_os("CHIP8_UPDATE_HEADER").is_some() {
+        std::fs::write(&header, GENERATED).unwrap();
+    } else {
+        panic!(
+            "{} is stale, rerun the tests with CHIP8_UPDATE_HEADER=1 to regenerate it",
+            header.display()
+        );
+    }
+}
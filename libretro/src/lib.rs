@@ -0,0 +1,362 @@
+mod ffi;
+
+use std::os::raw::{c_char, c_uint, c_void};
+use std::sync::Mutex;
+
+use chip8::{Chip8, Framebuffer, Screen, IO, STATE_SIZE};
+
+use ffi::*;
+
+const FRAME_RATE: f64 = 60.0;
+const SAMPLE_RATE: u32 = 44100;
+const SAMPLES_PER_FRAME: usize = (SAMPLE_RATE as usize) / (FRAME_RATE as usize);
+
+// Square wave played while the buzzer sounds
+const AUDIO_PITCH: f32 = 440.0;
+const AUDIO_AMPLITUDE: i16 = i16::MAX / 4;
+
+const COLOR_ON: u32 = 0x00ffffff;
+const COLOR_OFF: u32 = 0x00000000;
+
+// Gamepads get the keys most games use for movement and action, keyboards use the pad map
+const JOYPAD_MAP: [(c_uint, usize); 10] = [
+    (RETRO_DEVICE_ID_JOYPAD_UP, 0x2),
+    (RETRO_DEVICE_ID_JOYPAD_DOWN, 0x8),
+    (RETRO_DEVICE_ID_JOYPAD_LEFT, 0x4),
+    (RETRO_DEVICE_ID_JOYPAD_RIGHT, 0x6),
+    (RETRO_DEVICE_ID_JOYPAD_A, 0x5),
+    (RETRO_DEVICE_ID_JOYPAD_B, 0x0),
+    (RETRO_DEVICE_ID_JOYPAD_X, 0xa),
+    (RETRO_DEVICE_ID_JOYPAD_Y, 0xb),
+    (RETRO_DEVICE_ID_JOYPAD_START, 0xf),
+    (RETRO_DEVICE_ID_JOYPAD_SELECT, 0xe),
+];
+
+#[derive(Clone, Copy)]
+struct Callbacks {
+    environment: Option<RetroEnvironment>,
+    video_refresh: Option<RetroVideoRefresh>,
+    audio_sample_batch: Option<RetroAudioSampleBatch>,
+    input_poll: Option<RetroInputPoll>,
+    input_state: Option<RetroInputState>,
+}
+
+struct Core {
+    chip8: Chip8,
+    screen: Framebuffer,
+    rom: Vec<u8>,
+    video: Vec<u32>,
+    audio: Vec<i16>,
+    phase: f32,
+    halted: bool,
+}
+
+static CALLBACKS: Mutex<Callbacks> = Mutex::new(Callbacks {
+    environment: None,
+    video_refresh: None,
+    audio_sample_batch: None,
+    input_poll: None,
+    input_state: None,
+});
+
+static CORE: Mutex<Option<Core>> = Mutex::new(None);
+
+impl Core {
+    fn new(rom: &[u8]) -> Result<Self, chip8::Error> {
+        let mut chip8 = Chip8::new(None);
+        chip8.load_rom(rom, None)?;
+
+        let (width, height) = chip8.get_screen_size();
+
+        Ok(Self {
+            chip8,
+            screen: Framebuffer::new(width, height)?,
+            rom: rom.to_vec(),
+            video: vec![COLOR_OFF; width * height],
+            audio: vec![0; 2 * SAMPLES_PER_FRAME],
+            phase: 0.0,
+            halted: false,
+        })
+    }
+
+    fn run(&mut self, callbacks: &Callbacks) -> Result<(), chip8::Error> {
+        let pad = self.read_pad(callbacks);
+        let mut beep = false;
+
+        self.chip8.frame(&mut IO {
+            screen: &mut self.screen,
+            pad: &pad,
+            audio: &mut beep,
+        })?;
+
+        self.render_video();
+        self.render_audio(beep);
+
+        let (width, height) = self.screen.size();
+
+        unsafe {
+            if let Some(video_refresh) = callbacks.video_refresh {
+                let pitch = width * std::mem::size_of::<u32>();
+                video_refresh(self.video.as_ptr().cast(), width as c_uint, height as c_uint, pitch);
+            }
+
+            if let Some(audio_sample_batch) = callbacks.audio_sample_batch {
+                audio_sample_batch(self.audio.as_ptr(), SAMPLES_PER_FRAME);
+            }
+        }
+
+        Ok(())
+    }
+
+    fn read_pad(&self, callbacks: &Callbacks) -> [bool; 0x10] {
+        let mut pad = [false; 0x10];
+
+        let (input_poll, input_state) = match (callbacks.input_poll, callbacks.input_state) {
+            (Some(input_poll), Some(input_state)) => (input_poll, input_state),
+            _ => return pad,
+        };
+
+        unsafe {
+            input_poll();
+
+            // RETROK values of letters and digits are their lowercase ASCII codes
+            for (key, down) in self.chip8.get_pad_map().iter().zip(pad.iter_mut()) {
+                *down |= input_state(0, RETRO_DEVICE_KEYBOARD, 0, *key as c_uint) != 0;
+            }
+
+            for (id, key) in JOYPAD_MAP {
+                pad[key] |= input_state(0, RETRO_DEVICE_JOYPAD, 0, id) != 0;
+            }
+        }
+
+        pad
+    }
+
+    fn render_video(&mut self) {
+        let (width, _) = self.screen.size();
+
+        for (row, line) in self.screen.get_rows().iter().zip(self.video.chunks_mut(width)) {
+            for (x, px) in line.iter_mut().enumerate() {
+                *px = if (row >> x) & 1 != 0 { COLOR_ON } else { COLOR_OFF };
+            }
+        }
+    }
+
+    // The phase is kept across frames, so the wave stays continuous
+    fn render_audio(&mut self, beep: bool) {
+        let phase_inc = AUDIO_PITCH / (SAMPLE_RATE as f32);
+
+        for frame in self.audio.chunks_exact_mut(2) {
+            let sample = match (beep, self.phase < 0.5) {
+                (false, _) => 0,
+                (true, true) => AUDIO_AMPLITUDE,
+                (true, false) => -AUDIO_AMPLITUDE,
+            };
+
+            frame.fill(sample);
+            self.phase = (self.phase + phase_inc).fract();
+        }
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn retro_api_version() -> c_uint {
+    RETRO_API_VERSION
+}
+
+#[no_mangle]
+pub extern "C" fn retro_set_environment(cb: RetroEnvironment) {
+    CALLBACKS.lock().unwrap().environment = Some(cb);
+}
+
+#[no_mangle]
+pub extern "C" fn retro_set_video_refresh(cb: RetroVideoRefresh) {
+    CALLBACKS.lock().unwrap().video_refresh = Some(cb);
+}
+
+// Audio is only sent in batches
+#[no_mangle]
+pub extern "C" fn retro_set_audio_sample(_cb: RetroAudioSample) {}
+
+#[no_mangle]
+pub extern "C" fn retro_set_audio_sample_batch(cb: RetroAudioSampleBatch) {
+    CALLBACKS.lock().unwrap().audio_sample_batch = Some(cb);
+}
+
+#[no_mangle]
+pub extern "C" fn retro_set_input_poll(cb: RetroInputPoll) {
+    CALLBACKS.lock().unwrap().input_poll = Some(cb);
+}
+
+#[no_mangle]
+pub extern "C" fn retro_set_input_state(cb: RetroInputState) {
+    CALLBACKS.lock().unwrap().input_state = Some(cb);
+}
+
+#[no_mangle]
+pub extern "C" fn retro_init() {}
+
+#[no_mangle]
+pub extern "C" fn retro_deinit() {
+    *CORE.lock().unwrap() = None;
+}
+
+/// # Safety
+///
+/// `info` must point to a writable `retro_system_info`.
+#[no_mangle]
+pub unsafe extern "C" fn retro_get_system_info(info: *mut RetroSystemInfo) {
+    *info = RetroSystemInfo {
+        library_name: c"chip8".as_ptr(),
+        library_version: concat!(env!("CARGO_PKG_VERSION"), "\0").as_ptr().cast(),
+        valid_extensions: c"ch8|c8|rom".as_ptr(),
+        need_fullpath: false,
+        block_extract: false,
+    };
+}
+
+/// # Safety
+///
+/// `info` must point to a writable `retro_system_av_info`.
+#[no_mangle]
+pub unsafe extern "C" fn retro_get_system_av_info(info: *mut RetroSystemAvInfo) {
+    let (width, height) = Framebuffer::default().size();
+
+    *info = RetroSystemAvInfo {
+        geometry: RetroGameGeometry {
+            base_width: width as c_uint,
+            base_height: height as c_uint,
+            max_width: width as c_uint,
+            max_height: height as c_uint,
+            aspect_ratio: (width as f32) / (height as f32),
+        },
+        timing: RetroSystemTiming {
+            fps: FRAME_RATE,
+            sample_rate: SAMPLE_RATE as f64,
+        },
+    };
+}
+
+#[no_mangle]
+pub extern "C" fn retro_set_controller_port_device(_port: c_uint, _device: c_uint) {}
+
+#[no_mangle]
+pub extern "C" fn retro_reset() {
+    let mut core = CORE.lock().unwrap();
+
+    if let Some(reset) = core.as_ref().and_then(|core| Core::new(&core.rom).ok()) {
+        *core = Some(reset);
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn retro_run() {
+    // Frontends may call back into the core, so no lock on the callbacks is held while calling them
+    let callbacks = *CALLBACKS.lock().unwrap();
+
+    // Errors halt the machine, the frontend keeps presenting the last frame
+    if let Some(core) = CORE.lock().unwrap().as_mut().filter(|core| !core.halted) {
+        core.halted = core.run(&callbacks).is_err();
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn retro_serialize_size() -> usize {
+    STATE_SIZE
+}
+
+/// # Safety
+///
+/// `data` must be null or point to `size` writable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn retro_serialize(data: *mut c_void, size: usize) -> bool {
+    if data.is_null() {
+        return false;
+    }
+
+    let out = std::slice::from_raw_parts_mut(data.cast::<u8>(), size);
+
+    match CORE.lock().unwrap().as_ref() {
+        Some(core) => core.chip8.save_state(&core.screen, out).is_ok(),
+        None => false,
+    }
+}
+
+/// # Safety
+///
+/// `data` must be null or point to `size` readable bytes.
+#[no_mangle]
+pub unsafe extern "C" fn retro_unserialize(data: *const c_void, size: usize) -> bool {
+    if data.is_null() {
+        return false;
+    }
+
+    let state = std::slice::from_raw_parts(data.cast::<u8>(), size);
+
+    match CORE.lock().unwrap().as_mut() {
+        Some(core) => core.chip8.load_state(&mut core.screen, state).is_ok(),
+        None => false,
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn retro_cheat_reset() {}
+
+#[no_mangle]
+pub extern "C" fn retro_cheat_set(_index: c_uint, _enabled: bool, _code: *const c_char) {}
+
+/// # Safety
+///
+/// `game` must be null or point to a valid `retro_game_info`, with `size` readable bytes at `data`.
+#[no_mangle]
+pub unsafe extern "C" fn retro_load_game(game: *const RetroGameInfo) -> bool {
+    let game = match game.as_ref() {
+        Some(game) if !game.data.is_null() => game,
+        _ => return false,
+    };
+
+    let mut format = RETRO_PIXEL_FORMAT_XRGB8888;
+
+    let environment = CALLBACKS.lock().unwrap().environment;
+
+    if let Some(environment) = environment {
+        if !environment(RETRO_ENVIRONMENT_SET_PIXEL_FORMAT, (&mut format as *mut c_uint).cast()) {
+            return false;
+        }
+    }
+
+    let rom = std::slice::from_raw_parts(game.data.cast::<u8>(), game.size);
+
+    match Core::new(rom) {
+        Ok(core) => {
+            *CORE.lock().unwrap() = Some(core);
+            true
+        }
+        Err(_) => false,
+    }
+}
+
+#[no_mangle]
+pub extern "C" fn retro_load_game_special(_type: c_uint, _info: *const RetroGameInfo, _num: usize) -> bool {
+    false
+}
+
+#[no_mangle]
+pub extern "C" fn retro_unload_game() {
+    *CORE.lock().unwrap() = None;
+}
+
+#[no_mangle]
+pub extern "C" fn retro_get_region() -> c_uint {
+    RETRO_REGION_NTSC
+}
+
+#[no_mangle]
+pub extern "C" fn retro_get_memory_data(_id: c_uint) -> *mut c_void {
+    std::ptr::null_mut()
+}
+
+#[no_mangle]
+pub extern "C" fn retro_get_memory_size(_id: c_uint) -> usize {
+    0
+}
@@ -0,0 +1,245 @@
+use std::os::raw::{c_char, c_uint, c_void};
+use std::sync::Mutex;
+
+use libloading::{Library, Symbol};
+
+const RETRO_DEVICE_JOYPAD: c_uint = 1;
+const RETRO_DEVICE_KEYBOARD: c_uint = 3;
+const RETRO_DEVICE_ID_JOYPAD_A: c_uint = 8;
+
+// Draws the 0 glyph and sounds the buzzer for 5 ticks
+const ROM_BEEP: [u8; 10] = [0x60, 0x05, 0xf0, 0x18, 0xa0, 0x00, 0xd0, 0x15, 0x12, 0x08];
+
+// Waits for a key, then draws its glyph in the top left corner
+const ROM_KEY: [u8; 8] = [0xf0, 0x0a, 0xf0, 0x29, 0xd1, 0x25, 0x12, 0x06];
+
+// The core has global state, and callbacks cannot capture, so tests run one at a time
+static LOCK: Mutex<()> = Mutex::new(());
+static VIDEO: Mutex<Vec<u32>> = Mutex::new(Vec::new());
+static AUDIO: Mutex<Vec<i16>> = Mutex::new(Vec::new());
+static INPUT: Mutex<Vec<(c_uint, c_uint)>> = Mutex::new(Vec::new());
+
+type Environment = unsafe extern "C" fn(c_uint, *mut c_void) -> bool;
+type VideoRefresh = unsafe extern "C" fn(*const c_void, c_uint, c_uint, usize);
+type AudioSampleBatch = unsafe extern "C" fn(*const i16, usize) -> usize;
+type InputPoll = unsafe extern "C" fn();
+type InputState = unsafe extern "C" fn(c_uint, c_uint, c_uint, c_uint) -> i16;
+type SetCallback<T> = unsafe extern "C" fn(T);
+
+#[repr(C)]
+struct RetroGameInfo {
+    path: *const c_char,
+    data: *const c_void,
+    size: usize,
+    meta: *const c_char,
+}
+
+struct Frontend {
+    lib: Library,
+}
+
+unsafe extern "C" fn environment(_cmd: c_uint, _data: *mut c_void) -> bool {
+    true
+}
+
+unsafe extern "C" fn video_refresh(data: *const c_void, width: c_uint, height: c_uint, pitch: usize) {
+    assert_eq!((width, height, pitch), (64, 32, 256));
+
+    let pixels = std::slice::from_raw_parts(data.cast::<u32>(), (width * height) as usize);
+    *VIDEO.lock().unwrap() = pixels.to_vec();
+}
+
+unsafe extern "C" fn audio_sample_batch(data: *const i16, frames: usize) -> usize {
+    *AUDIO.lock().unwrap() = std::slice::from_raw_parts(data, 2 * frames).to_vec();
+    frames
+}
+
+unsafe extern "C" fn input_poll() {}
+
+unsafe extern "C" fn input_state(_port: c_uint, device: c_uint, _index: c_uint, id: c_uint) -> i16 {
+    INPUT.lock().unwrap().contains(&(device, id)) as i16
+}
+
+impl Frontend {
+    fn new() -> Self {
+        let dir = std::env::current_exe()
+            .unwrap()
+            .parent()
+            .unwrap()
+            .parent()
+            .unwrap()
+            .to_owned();
+        let path = dir.join(format!(
+            "{}chip8_libretro{}",
+            std::env::consts::DLL_PREFIX,
+            std::env::consts::DLL_SUFFIX
+        ));
+
+        let lib = unsafe { Library::new(path).unwrap() };
+        let frontend = Self { lib };
+
+        unsafe {
+            frontend.symbol::<SetCallback<Environment>>(b"retro_set_environment")(environment);
+            frontend.symbol::<SetCallback<VideoRefresh>>(b"retro_set_video_refresh")(video_refresh);
+            frontend.symbol::<SetCallback<AudioSampleBatch>>(b"retro_set_audio_sample_batch")(audio_sample_batch);
+            frontend.symbol::<SetCallback<InputPoll>>(b"retro_set_input_poll")(input_poll);
+            frontend.symbol::<SetCallback<InputState>>(b"retro_set_input_state")(input_state);
+            frontend.symbol::<unsafe extern "C" fn()>(b"retro_init")();
+        }
+
+        INPUT.lock().unwrap().clear();
+
+        frontend
+    }
+
+    unsafe fn symbol<T>(&self, name: &[u8]) -> Symbol<'_, T> {
+        self.lib.get(name).unwrap()
+    }
+
+    fn load_game(&self, rom: &[u8]) -> bool {
+        let game = RetroGameInfo {
+            path: std::ptr::null(),
+            data: rom.as_ptr().cast(),
+            size: rom.len(),
+            meta: std::ptr::null(),
+        };
+
+        unsafe { self.symbol::<unsafe extern "C" fn(*const RetroGameInfo) -> bool>(b"retro_load_game")(&game) }
+    }
+
+    fn run(&self, frames: usize) {
+        for _ in 0..frames {
+            unsafe { self.symbol::<unsafe extern "C" fn()>(b"retro_run")() };
+        }
+    }
+
+    fn serialize(&self) -> Vec<u8> {
+        unsafe {
+            let size = self.symbol::<unsafe extern "C" fn() -> usize>(b"retro_serialize_size")();
+            let mut state = vec![0x00; size];
+            let ok = self.symbol::<unsafe extern "C" fn(*mut c_void, usize) -> bool>(b"retro_serialize")(
+                state.as_mut_ptr().cast(),
+                size,
+            );
+
+            assert!(ok);
+            state
+        }
+    }
+
+    fn unserialize(&self, state: &[u8]) -> bool {
+        unsafe {
+            self.symbol::<unsafe extern "C" fn(*const c_void, usize) -> bool>(b"retro_unserialize")(
+                state.as_ptr().cast(),
+                state.len(),
+            )
+        }
+    }
+
+    fn pixel(x: usize, y: usize) -> bool {
+        VIDEO.lock().unwrap()[y * 64 + x] & 0x00ffffff != 0
+    }
+
+    fn is_beeping() -> bool {
+        AUDIO.lock().unwrap().iter().any(|sample| *sample != 0)
+    }
+}
+
+impl Drop for Frontend {
+    fn drop(&mut self) {
+        unsafe {
+            self.symbol::<unsafe extern "C" fn()>(b"retro_unload_game")();
+            self.symbol::<unsafe extern "C" fn()>(b"retro_deinit")();
+        }
+    }
+}
+
+#[test]
+fn video_and_audio() {
+    let _lock = LOCK.lock().unwrap();
+    let frontend = Frontend::new();
+
+    assert_eq!(
+        unsafe { frontend.symbol::<unsafe extern "C" fn() -> c_uint>(b"retro_api_version")() },
+        1
+    );
+    assert!(frontend.load_game(&ROM_BEEP));
+
+    frontend.run(1);
+
+    // Top row of the 0 glyph, drawn at x = 5
+    assert!((5..9).all(|x| Frontend::pixel(x, 0)));
+    assert!(!Frontend::pixel(4, 0) && !Frontend::pixel(9, 0));
+    assert_eq!(AUDIO.lock().unwrap().len(), 2 * 735);
+    assert!(Frontend::is_beeping());
+
+    frontend.run(5);
+    assert!(!Frontend::is_beeping());
+}
+
+#[test]
+fn serialization() {
+    let _lock = LOCK.lock().unwrap();
+    let frontend = Frontend::new();
+
+    assert!(frontend.load_game(&ROM_BEEP));
+    frontend.run(1);
+
+    let state = frontend.serialize();
+
+    frontend.run(10);
+    assert!(!Frontend::is_beeping());
+    assert_ne!(frontend.serialize(), state);
+
+    // Timers and the screen come back with the state
+    assert!(frontend.unserialize(&state));
+    assert_eq!(frontend.serialize(), state);
+
+    frontend.run(1);
+    assert!(Frontend::is_beeping());
+    assert!((5..9).all(|x| Frontend::pixel(x, 0)));
+
+    assert!(!frontend.unserialize(&state[1..]));
+
+    // Null buffers are rejected, whatever the size
+    unsafe {
+        for size in [0, state.len()] {
+            assert!(!frontend.symbol::<unsafe extern "C" fn(*mut c_void, usize) -> bool>(
+                b"retro_serialize"
+            )(std::ptr::null_mut(), size));
+            assert!(!frontend.symbol::<unsafe extern "C" fn(*const c_void, usize) -> bool>(
+                b"retro_unserialize"
+            )(std::ptr::null(), size));
+        }
+    }
+}
+
+#[test]
+fn input() {
+    let _lock = LOCK.lock().unwrap();
+    let frontend = Frontend::new();
+
+    assert!(frontend.load_game(&ROM_KEY));
+    frontend.run(2);
+    assert!(!Frontend::pixel(0, 0));
+
+    // W is mapped to key 5
+    INPUT.lock().unwrap().push((RETRO_DEVICE_KEYBOARD, 'w' as c_uint));
+    frontend.run(1);
+
+    // Glyph 5 is 0xf0, 0x80, 0xf0, 0x10, 0xf0
+    assert!(Frontend::pixel(0, 1) && !Frontend::pixel(3, 1));
+    assert!(!Frontend::pixel(0, 3) && Frontend::pixel(3, 3));
+
+    // Gamepads reach the pad through the joypad map
+    INPUT.lock().unwrap().clear();
+    assert!(frontend.load_game(&ROM_KEY));
+    frontend.run(2);
+
+    INPUT
+        .lock()
+        .unwrap()
+        .push((RETRO_DEVICE_JOYPAD, RETRO_DEVICE_ID_JOYPAD_A));
+    frontend.run(1);
+    assert!(Frontend::pixel(0, 1) && !Frontend::pixel(3, 1));
+}
@@ -0,0 +1,364 @@
+use std::collections::{BTreeMap, BTreeSet};
+use std::io;
+
+use crate::PROGRAM_START;
+
+const OPCODE_SIZE: u16 = 2;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BlockExit {
+    Next,
+    Jump(u16),
+    Call(u16),
+    Skip,
+    Return,
+    ComputedJump(u16),
+    Invalid([u8; 4]),
+    EndOfRom,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BasicBlock {
+    pub start: u16,
+    pub end: u16,
+    pub exit: BlockExit,
+    pub successors: Vec<u16>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Routine {
+    pub entry: u16,
+    pub blocks: Vec<u16>,
+    pub calls: Vec<u16>,
+}
+
+#[derive(Debug, Clone)]
+pub struct Analysis {
+    start: u16,
+    end: u16,
+    blocks: BTreeMap<u16, BasicBlock>,
+    routines: BTreeMap<u16, Routine>,
+}
+
+impl Analysis {
+    pub fn new(rom: &[u8]) -> Self {
+        let start = PROGRAM_START;
+        let end = start.saturating_add(rom.len().min(u16::MAX as usize) as u16);
+
+        let fetch = |addr: u16| -> Option<[u8; 4]> {
+            let offset = addr.checked_sub(start)? as usize;
+            let hi = *rom.get(offset)?;
+            let lo = *rom.get(offset + 1)?;
+            Some([(hi >> 4) & 0xf, hi & 0xf, (lo >> 4) & 0xf, lo & 0xf])
+        };
+
+        // Discover reachable instructions, and the addresses starting a basic block
+        let mut leaders = BTreeSet::from([start]);
+        let mut visited = BTreeSet::new();
+        let mut pending = vec![start];
+
+        while let Some(addr) = pending.pop() {
+            if !visited.insert(addr) {
+                continue;
+            }
+
+            let exit = fetch(addr).map(decode).unwrap_or(BlockExit::EndOfRom);
+
+            for target in successors(addr, exit) {
+                if exit != BlockExit::Next {
+                    leaders.insert(target);
+                }
+                pending.push(target);
+            }
+
+            if let BlockExit::Call(target) = exit {
+                leaders.insert(target);
+                pending.push(target);
+            }
+        }
+
+        // Group instructions into basic blocks
+        let mut blocks = BTreeMap::new();
+
+        for leader in leaders.iter().copied().filter(|addr| visited.contains(addr)) {
+            let mut addr = leader;
+
+            let exit = loop {
+                let exit = fetch(addr).map(decode).unwrap_or(BlockExit::EndOfRom);
+                let next = addr.wrapping_add(OPCODE_SIZE);
+
+                if exit != BlockExit::Next || leaders.contains(&next) {
+                    break exit;
+                }
+
+                addr = next;
+            };
+
+            let successors = successors(addr, exit);
+            let end = match exit {
+                BlockExit::EndOfRom => addr,
+                _ => addr.wrapping_add(OPCODE_SIZE),
+            };
+
+            blocks.insert(
+                leader,
+                BasicBlock {
+                    start: leader,
+                    end,
+                    exit,
+                    successors,
+                },
+            );
+        }
+
+        // Routines own the blocks reachable from their entry, without following calls
+        let entries: BTreeSet<_> = [start]
+            .into_iter()
+            .chain(blocks.values().filter_map(|block| match block.exit {
+                BlockExit::Call(target) => Some(target),
+                _ => None,
+            }))
+            .collect();
+
+        let routines = entries
+            .iter()
+            .copied()
+            .map(|entry| {
+                let mut owned = BTreeSet::new();
+                let mut calls = BTreeSet::new();
+                let mut pending = vec![entry];
+
+                while let Some(addr) = pending.pop() {
+                    if let Some(block) = blocks.get(&addr) {
+                        if owned.insert(addr) {
+                            pending.extend(block.successors.iter().copied());
+
+                            if let BlockExit::Call(target) = block.exit {
+                                calls.insert(target);
+                            }
+                        }
+                    }
+                }
+
+                let routine = Routine {
+                    entry,
+                    blocks: owned.into_iter().collect(),
+                    calls: calls.into_iter().collect(),
+                };
+
+                (entry, routine)
+            })
+            .collect();
+
+        Self {
+            start,
+            end,
+            blocks,
+            routines,
+        }
+    }
+
+    pub fn get_blocks(&self) -> impl Iterator<Item = &BasicBlock> {
+        self.blocks.values()
+    }
+
+    pub fn get_routines(&self) -> impl Iterator<Item = &Routine> {
+        self.routines.values()
+    }
+
+    pub fn get_computed_jumps(&self) -> Vec<u16> {
+        self.blocks
+            .values()
+            .filter(|block| matches!(block.exit, BlockExit::ComputedJump(_)))
+            .map(|block| block.end.wrapping_sub(OPCODE_SIZE))
+            .collect()
+    }
+
+    // ROM ranges never reached by static analysis, end address excluded
+    pub fn get_unreachable(&self) -> Vec<(u16, u16)> {
+        let mut regions = Vec::new();
+        let mut addr = self.start;
+
+        for block in self.blocks.values() {
+            if block.start >= self.end {
+                break;
+            }
+
+            if block.start > addr {
+                regions.push((addr, block.start));
+            }
+
+            addr = addr.max(block.end);
+        }
+
+        if addr < self.end {
+            regions.push((addr, self.end));
+        }
+
+        regions
+    }
+
+    pub fn write_dot<W: io::Write>(&self, mut w: W) -> io::Result<()> {
+        writeln!(w, "digraph rom {{")?;
+        writeln!(w, "    node [shape=box, fontname=monospace];")?;
+
+        for routine in self.routines.values() {
+            writeln!(w, "    subgraph cluster_{:04x} {{", routine.entry)?;
+            writeln!(w, "        label=\"{}\";", routine_name(self.start, routine.entry))?;
+
+            for addr in &routine.blocks {
+                let block = &self.blocks[addr];
+                let style = match block.exit {
+                    BlockExit::ComputedJump(_) | BlockExit::Invalid(_) | BlockExit::EndOfRom => ", color=red",
+                    _ => "",
+                };
+
+                writeln!(
+                    w,
+                    "        \"{:04x}_{:04x}\" [label=\"0x{:04x}-0x{:04x}\\n{}\"{}];",
+                    routine.entry,
+                    block.start,
+                    block.start,
+                    block.end,
+                    exit_name(block.exit),
+                    style
+                )?;
+            }
+
+            for addr in &routine.blocks {
+                for target in &self.blocks[addr].successors {
+                    writeln!(
+                        w,
+                        "        \"{:04x}_{:04x}\" -> \"{:04x}_{:04x}\";",
+                        routine.entry, addr, routine.entry, target
+                    )?;
+                }
+            }
+
+            writeln!(w, "    }}")?;
+        }
+
+        // Call graph edges, from the calling block to the callee entry
+        for routine in self.routines.values() {
+            for addr in &routine.blocks {
+                if let BlockExit::Call(target) = self.blocks[addr].exit {
+                    writeln!(
+                        w,
+                        "    \"{:04x}_{:04x}\" -> \"{:04x}_{:04x}\" [style=dashed];",
+                        routine.entry, addr, target, target
+                    )?;
+                }
+            }
+        }
+
+        writeln!(w, "}}")?;
+
+        w.flush()
+    }
+
+    pub fn write_json<W: io::Write>(&self, mut w: W) -> io::Result<()> {
+        writeln!(w, "{{")?;
+        writeln!(w, "  \"start\": {},", self.start)?;
+        writeln!(w, "  \"end\": {},", self.end)?;
+        writeln!(w, "  \"blocks\": [")?;
+
+        for (i, block) in self.blocks.values().enumerate() {
+            let sep = if i + 1 < self.blocks.len() { "," } else { "" };
+            writeln!(
+                w,
+                "    {{ \"start\": {}, \"end\": {}, \"exit\": \"{}\", \"successors\": [{}] }}{}",
+                block.start,
+                block.end,
+                exit_name(block.exit),
+                join(&block.successors),
+                sep
+            )?;
+        }
+
+        writeln!(w, "  ],")?;
+        writeln!(w, "  \"routines\": [")?;
+
+        for (i, routine) in self.routines.values().enumerate() {
+            let sep = if i + 1 < self.routines.len() { "," } else { "" };
+            writeln!(
+                w,
+                "    {{ \"entry\": {}, \"blocks\": [{}], \"calls\": [{}] }}{}",
+                routine.entry,
+                join(&routine.blocks),
+                join(&routine.calls),
+                sep
+            )?;
+        }
+
+        writeln!(w, "  ],")?;
+        writeln!(w, "  \"computed_jumps\": [{}],", join(&self.get_computed_jumps()))?;
+
+        let unreachable: Vec<_> = self
+            .get_unreachable()
+            .iter()
+            .map(|(start, end)| format!("{{ \"start\": {}, \"end\": {} }}", start, end))
+            .collect();
+
+        writeln!(w, "  \"unreachable\": [{}]", unreachable.join(", "))?;
+        writeln!(w, "}}")?;
+
+        w.flush()
+    }
+}
+
+fn decode(opcode: [u8; 4]) -> BlockExit {
+    let nnn = ((opcode[1] as u16) << 8) | ((opcode[2] as u16) << 4) | (opcode[3] as u16);
+
+    match opcode {
+        [0x0, 0x0, 0x0, 0x0] | [0x0, 0x0, 0xe, 0x0] => BlockExit::Next,
+        [0x0, 0x0, 0xe, 0xe] => BlockExit::Return,
+        [0x1, _, _, _] => BlockExit::Jump(nnn),
+        [0x2, _, _, _] => BlockExit::Call(nnn),
+        [0x3, _, _, _] | [0x4, _, _, _] | [0x5, _, _, 0x0] | [0x9, _, _, 0x0] => BlockExit::Skip,
+        [0x6, _, _, _] | [0x7, _, _, _] | [0xa, _, _, _] | [0xc, _, _, _] | [0xd, _, _, _] => BlockExit::Next,
+        [0x8, _, _, 0x0..=0x7] | [0x8, _, _, 0xe] => BlockExit::Next,
+        [0xb, _, _, _] => BlockExit::ComputedJump(nnn),
+        [0xe, _, 0x9, 0xe] | [0xe, _, 0xa, 0x1] => BlockExit::Skip,
+        [0xf, _, 0x0, 0x7] | [0xf, _, 0x0, 0xa] | [0xf, _, 0x1, 0x5] | [0xf, _, 0x1, 0x8] => BlockExit::Next,
+        [0xf, _, 0x1, 0xe] | [0xf, _, 0x2, 0x9] | [0xf, _, 0x3, 0x3] | [0xf, _, 0x5, 0x5] | [0xf, _, 0x6, 0x5] => {
+            BlockExit::Next
+        }
+        _ => BlockExit::Invalid(opcode),
+    }
+}
+
+fn successors(addr: u16, exit: BlockExit) -> Vec<u16> {
+    let next = addr.wrapping_add(OPCODE_SIZE);
+
+    match exit {
+        BlockExit::Next | BlockExit::Call(_) => vec![next],
+        BlockExit::Jump(target) => vec![target],
+        BlockExit::Skip => vec![next, next.wrapping_add(OPCODE_SIZE)],
+        BlockExit::Return | BlockExit::ComputedJump(_) | BlockExit::Invalid(_) | BlockExit::EndOfRom => vec![],
+    }
+}
+
+fn join(addrs: &[u16]) -> String {
+    addrs.iter().map(|addr| addr.to_string()).collect::<Vec<_>>().join(", ")
+}
+
+fn routine_name(start: u16, entry: u16) -> String {
+    if entry == start {
+        String::from("main")
+    } else {
+        format!("sub_{:04x}", entry)
+    }
+}
+
+fn exit_name(exit: BlockExit) -> String {
+    match exit {
+        BlockExit::Next => String::from("next"),
+        BlockExit::Jump(addr) => format!("jump 0x{:04x}", addr),
+        BlockExit::Call(addr) => format!("call 0x{:04x}", addr),
+        BlockExit::Skip => String::from("skip"),
+        BlockExit::Return => String::from("return"),
+        BlockExit::ComputedJump(addr) => format!("computed jump 0x{:04x}+V0", addr),
+        BlockExit::Invalid(op) => format!("invalid {:x}{:x}{:x}{:x}", op[0], op[1], op[2], op[3]),
+        BlockExit::EndOfRom => String::from("end of rom"),
+    }
+}
@@ -0,0 +1,126 @@
+use std::io;
+use std::time;
+
+use crate::error::Error;
+use crate::{AUDIO_PITCH, AUDIO_SAMPLE_RATE, AUDIO_VOLUME, TIMER_FREQUENCY};
+
+#[derive(Debug, Default, Clone)]
+pub struct Timeline {
+    transitions: Vec<(u64, bool)>,
+    ticks: u64,
+    beep: bool,
+}
+
+#[derive(Debug)]
+pub struct AudioRenderer {
+    rate: u32,
+    pitch: f32,
+    volume: f32,
+}
+
+impl Timeline {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn push(&mut self, beep: bool) {
+        if beep != self.beep {
+            self.transitions.push((self.ticks, beep));
+            self.beep = beep;
+        }
+
+        self.ticks += 1;
+    }
+
+    pub fn get_transitions(&self) -> &[(u64, bool)] {
+        &self.transitions
+    }
+
+    pub fn get_ticks(&self) -> u64 {
+        self.ticks
+    }
+
+    pub fn get_duration(&self) -> time::Duration {
+        time::Duration::from_secs(self.ticks).div_f32(TIMER_FREQUENCY)
+    }
+}
+
+impl AudioRenderer {
+    pub fn new(rate: Option<u32>) -> Result<Self, Error> {
+        let rate = rate.unwrap_or(AUDIO_SAMPLE_RATE);
+
+        if rate == 0 || rate > (i32::MAX as u32) {
+            return Err(Error::InvalidSampleRate(rate));
+        }
+
+        Ok(Self {
+            rate,
+            pitch: AUDIO_PITCH,
+            volume: AUDIO_VOLUME,
+        })
+    }
+
+    pub fn get_rate(&self) -> u32 {
+        self.rate
+    }
+
+    pub fn render(&self, timeline: &Timeline) -> Vec<i16> {
+        let mut samples = vec![0; self.sample_index(timeline.ticks)];
+
+        let phase_inc = self.pitch / (self.rate as f32);
+        let mut phase = 0.0;
+
+        // Each transition starts a segment lasting until the next one, or the end of the timeline
+        let ends = timeline.transitions.iter().skip(1).map(|(tick, _)| *tick);
+
+        for (&(start, beep), end) in timeline.transitions.iter().zip(ends.chain([timeline.ticks])) {
+            if !beep {
+                continue;
+            }
+
+            for sample in &mut samples[self.sample_index(start)..self.sample_index(end)] {
+                let level = if phase <= 0.5 { self.volume } else { -self.volume };
+                *sample = (level * (i16::MAX as f32)) as i16;
+                phase = (phase + phase_inc) % 1.0;
+            }
+        }
+
+        samples
+    }
+
+    pub fn write_wav<W: io::Write>(&self, timeline: &Timeline, mut w: W) -> io::Result<()> {
+        let samples = self.render(timeline);
+
+        let data_size = u32::try_from(samples.len() * 2)
+            .ok()
+            .filter(|size| *size <= u32::MAX - 36)
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "audio recording too long for WAV"))?;
+
+        // RIFF header, followed by a 16-bit mono PCM format chunk
+        w.write_all(b"RIFF")?;
+        w.write_all(&(36 + data_size).to_le_bytes())?;
+        w.write_all(b"WAVE")?;
+        w.write_all(b"fmt ")?;
+        w.write_all(&16u32.to_le_bytes())?;
+        w.write_all(&1u16.to_le_bytes())?;
+        w.write_all(&1u16.to_le_bytes())?;
+        w.write_all(&self.rate.to_le_bytes())?;
+        w.write_all(&(self.rate * 2).to_le_bytes())?;
+        w.write_all(&2u16.to_le_bytes())?;
+        w.write_all(&16u16.to_le_bytes())?;
+
+        // Sample data
+        w.write_all(b"data")?;
+        w.write_all(&data_size.to_le_bytes())?;
+
+        for sample in samples {
+            w.write_all(&sample.to_le_bytes())?;
+        }
+
+        w.flush()
+    }
+
+    fn sample_index(&self, tick: u64) -> usize {
+        ((tick as f64) * (self.rate as f64) / (TIMER_FREQUENCY as f64)) as usize
+    }
+}
@@ -0,0 +1,241 @@
+use std::num::NonZeroUsize;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+use crate::error::Error;
+use crate::io::{Framebuffer, IO};
+use crate::state::STATE_SIZE;
+use crate::{unpack_keys, Chip8, SCREEN_SIZE};
+
+// Many machines running the same ROM, stepped in bulk across threads
+#[derive(Debug)]
+pub struct Batch {
+    rom: Vec<u8>,
+    freq: Option<f32>,
+    ipf: Option<u32>,
+    instances: Vec<Instance>,
+    threads: usize,
+    workers: Vec<Worker>,
+}
+
+#[derive(Debug)]
+struct Instance {
+    chip8: Chip8,
+    screen: Framebuffer,
+    keys: u16,
+    halted: Option<Error>,
+}
+
+// Instances are handed over to a worker for each step, then sent back
+#[derive(Debug)]
+struct Worker {
+    jobs: Sender<(Vec<Instance>, u32)>,
+    done: Receiver<Vec<Instance>>,
+}
+
+impl Instance {
+    fn new(rom: &[u8], freq: Option<f32>, ipf: Option<u32>, seed: Option<u16>) -> Result<Self, Error> {
+        let mut chip8 = Chip8::new(freq);
+        let (width, height) = chip8.get_screen_size();
+
+        if let Some(ipf) = ipf {
+            chip8.set_ipf(ipf)?;
+        }
+
+        chip8.load_rom(rom, seed)?;
+
+        Ok(Self {
+            chip8,
+            screen: Framebuffer::new(width, height)?,
+            keys: 0x0000,
+            halted: None,
+        })
+    }
+
+    // Errors halt the instance until it is reset
+    fn run(&mut self, frames: u32) {
+        let pad = unpack_keys(self.keys);
+
+        for _ in 0..frames {
+            if self.halted.is_some() {
+                return;
+            }
+
+            self.halted = self
+                .chip8
+                .frame(&mut IO {
+                    screen: &mut self.screen,
+                    pad: &pad,
+                    audio: &mut false,
+                })
+                .err();
+        }
+    }
+}
+
+impl Worker {
+    // The thread exits once the batch drops its end of the job channel
+    fn spawn() -> Self {
+        let (jobs, pending) = mpsc::channel::<(Vec<Instance>, u32)>();
+        let (finished, done) = mpsc::channel();
+
+        thread::spawn(move || {
+            for (mut instances, frames) in pending {
+                instances.iter_mut().for_each(|instance| instance.run(frames));
+
+                if finished.send(instances).is_err() {
+                    return;
+                }
+            }
+        });
+
+        Self { jobs, done }
+    }
+}
+
+impl Batch {
+    pub fn new(rom: &[u8], len: usize, freq: Option<f32>) -> Result<Self, Error> {
+        Ok(Self {
+            rom: rom.to_vec(),
+            freq,
+            ipf: None,
+            instances: (0..len)
+                .map(|_| Instance::new(rom, freq, None, None))
+                .collect::<Result<_, _>>()?,
+            threads: thread::available_parallelism().map(NonZeroUsize::get).unwrap_or(1),
+            workers: Vec::new(),
+        })
+    }
+
+    pub fn len(&self) -> usize {
+        self.instances.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.instances.is_empty()
+    }
+
+    // Defaults to the available parallelism
+    pub fn set_threads(&mut self, threads: Option<usize>) -> Result<(), Error> {
+        self.threads = match threads {
+            Some(0) => return Err(Error::InvalidThreadCount(0)),
+            Some(threads) => threads,
+            None => thread::available_parallelism().map(NonZeroUsize::get).unwrap_or(1),
+        };
+
+        Ok(())
+    }
+
+    pub fn get_threads(&self) -> usize {
+        self.threads
+    }
+
+    // Applies to all instances, including the ones reset later
+    pub fn set_ipf(&mut self, ipf: u32) -> Result<(), Error> {
+        for instance in &mut self.instances {
+            instance.chip8.set_ipf(ipf)?;
+        }
+
+        self.ipf = Some(ipf);
+        Ok(())
+    }
+
+    // Restarts an instance on a fresh machine
+    pub fn reset(&mut self, index: usize, seed: Option<u16>) -> Result<(), Error> {
+        let instance = self.instances.get_mut(index).ok_or(Error::InstanceOutOfRange(index))?;
+        *instance = Instance::new(&self.rom, self.freq, self.ipf, seed)?;
+        Ok(())
+    }
+
+    // Keys held by an instance as a bitmask, until changed
+    pub fn set_keys(&mut self, index: usize, keys: u16) -> Result<(), Error> {
+        let instance = self.instances.get_mut(index).ok_or(Error::InstanceOutOfRange(index))?;
+        instance.keys = keys;
+        Ok(())
+    }
+
+    pub fn get_chip8(&self, index: usize) -> Option<&Chip8> {
+        self.instances.get(index).map(|instance| &instance.chip8)
+    }
+
+    pub fn get_screen(&self, index: usize) -> Option<&Framebuffer> {
+        self.instances.get(index).map(|instance| &instance.screen)
+    }
+
+    // The error that halted an instance, if any
+    pub fn get_error(&self, index: usize) -> Option<&Error> {
+        self.instances.get(index).and_then(|instance| instance.halted.as_ref())
+    }
+
+    // Instances are split in one contiguous chunk per worker, halted instances are skipped until reset
+    pub fn step(&mut self, frames: u32) {
+        // Workers are kept across steps, and only respawned when the thread count changes
+        if self.workers.len() != self.threads {
+            self.workers = (0..self.threads).map(|_| Worker::spawn()).collect();
+        }
+
+        let chunk = self.instances.len().div_ceil(self.threads).max(1);
+        let mut instances = std::mem::take(&mut self.instances);
+        let mut busy = 0;
+
+        while !instances.is_empty() {
+            let rest = instances.split_off(chunk.min(instances.len()));
+            self.workers[busy]
+                .jobs
+                .send((instances, frames))
+                .expect("batch thread panicked");
+
+            instances = rest;
+            busy += 1;
+        }
+
+        for worker in &self.workers[..busy] {
+            self.instances
+                .extend(worker.done.recv().expect("batch thread panicked"));
+        }
+    }
+
+    pub fn get_screen_size(&self) -> (usize, usize) {
+        SCREEN_SIZE
+    }
+
+    pub fn to_framebuffers(&self) -> Vec<bool> {
+        let mut pixels = vec![false; self.len() * SCREEN_SIZE.0 * SCREEN_SIZE.1];
+        self.write_framebuffers(&mut pixels).unwrap();
+        pixels
+    }
+
+    // Row major screens, one after the other
+    pub fn write_framebuffers(&self, out: &mut [bool]) -> Result<(), Error> {
+        let pixels = SCREEN_SIZE.0 * SCREEN_SIZE.1;
+
+        if out.len() != self.len() * pixels {
+            return Err(Error::InvalidBufferSize(out.len(), self.len() * pixels));
+        }
+
+        for (instance, out) in self.instances.iter().zip(out.chunks_exact_mut(pixels)) {
+            instance.screen.write_bools(out);
+        }
+
+        Ok(())
+    }
+
+    pub fn to_states(&self) -> Vec<u8> {
+        let mut states = vec![0x00; self.len() * STATE_SIZE];
+        self.write_states(&mut states).unwrap();
+        states
+    }
+
+    // Save states, one after the other
+    pub fn write_states(&self, out: &mut [u8]) -> Result<(), Error> {
+        if out.len() != self.len() * STATE_SIZE {
+            return Err(Error::InvalidBufferSize(out.len(), self.len() * STATE_SIZE));
+        }
+
+        for (instance, out) in self.instances.iter().zip(out.chunks_exact_mut(STATE_SIZE)) {
+            instance.chip8.save_state(&instance.screen, out)?;
+        }
+
+        Ok(())
+    }
+}
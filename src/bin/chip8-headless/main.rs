@@ -0,0 +1,84 @@
+use std::io::{BufRead, Write};
+
+use clap::Parser;
+use serde_json::{json, Value};
+
+use options::Options;
+use session::{Command, Session};
+
+mod options;
+mod session;
+
+fn main() {
+    try_main().unwrap_or_else(|err| {
+        eprintln!("Error: {}", err);
+        std::process::exit(1);
+    });
+}
+
+fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+    let options = Options::parse_from(std::env::args());
+    let mut session = Session::new(options.freq, options.ipf)?;
+
+    if let Some(path) = &options.rom {
+        session.load(&std::fs::read(path)?, options.seed)?;
+    }
+
+    let stdin = std::io::stdin().lock();
+    let mut stdout = std::io::stdout().lock();
+
+    // One request per line, each answered by exactly one response line
+    for line in stdin.lines() {
+        let line = line?;
+
+        if line.trim().is_empty() {
+            continue;
+        }
+
+        let (response, quit) = process(&mut session, &line);
+
+        writeln!(stdout, "{}", response)?;
+        stdout.flush()?;
+
+        if quit {
+            break;
+        }
+    }
+
+    Ok(())
+}
+
+// Request ids are echoed back as is, so that clients may pipeline requests
+fn process(session: &mut Session, line: &str) -> (Value, bool) {
+    let request = match serde_json::from_str::<Value>(line) {
+        Ok(request) => request,
+        Err(err) => return (failure(Value::Null, err.to_string()), false),
+    };
+
+    let mut request = match request {
+        Value::Object(request) => request,
+        _ => return (failure(Value::Null, "request is not an object".into()), false),
+    };
+
+    let id = request.remove("id").unwrap_or(Value::Null);
+
+    let command = match serde_json::from_value::<Command>(Value::Object(request)) {
+        Ok(command) => command,
+        Err(err) => return (failure(id, err.to_string()), false),
+    };
+
+    let quit = matches!(command, Command::Quit);
+
+    match session.execute(command) {
+        Ok(mut fields) => {
+            fields.insert("id".into(), id);
+            fields.insert("ok".into(), Value::Bool(true));
+            (Value::Object(fields), quit)
+        }
+        Err(err) => (failure(id, err.to_string()), false),
+    }
+}
+
+fn failure(id: Value, error: String) -> Value {
+    json!({ "id": id, "ok": false, "error": error })
+}
@@ -0,0 +1,43 @@
+use std::fmt;
+
+use clap::Parser;
+
+/// Another CHIP-8 toy emulator in Rust, driven by JSON commands on stdin
+#[derive(Debug, Parser)]
+#[clap(name = "CHIP8")]
+pub struct Options {
+    /// CPU Frequency (in hertz)
+    #[clap(long)]
+    pub freq: Option<f32>,
+    /// Run exactly this many instructions per 60 Hz frame, instead of a CPU frequency
+    #[clap(long, conflicts_with = "freq")]
+    pub ipf: Option<u32>,
+    /// CPU PRNG seed (in hexadecimal)
+    #[clap(long, parse(try_from_str = parse_seed))]
+    pub seed: Option<u16>,
+    /// Path to CHIP-8 ROM loaded before reading commands
+    pub rom: Option<std::path::PathBuf>,
+}
+
+#[derive(Debug)]
+pub enum OptionError {
+    InvalidSeed(String),
+}
+
+fn parse_seed(src: &str) -> Result<u16, OptionError> {
+    src.strip_prefix("0x")
+        .and_then(|hex| u16::from_str_radix(hex, 16).ok())
+        .ok_or_else(|| OptionError::InvalidSeed(src.into()))
+}
+
+impl fmt::Display for OptionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidSeed(seed) => {
+                write!(f, "invalid seed '{}', expected format is 0xXXXX", seed)
+            }
+        }
+    }
+}
+
+impl std::error::Error for OptionError {}
@@ -0,0 +1,202 @@
+use std::path::PathBuf;
+
+use serde::Deserialize;
+use serde_json::{json, Map, Value};
+
+use chip8::{Chip8, Framebuffer, KeyEvent, Screen, IO, MEMORY_SIZE};
+
+// Commands are objects tagged by their "cmd" field, counts default to one
+#[derive(Debug, Deserialize)]
+#[serde(tag = "cmd", rename_all = "snake_case", deny_unknown_fields)]
+pub enum Command {
+    Load {
+        path: Option<PathBuf>,
+        rom: Option<Vec<u8>>,
+        seed: Option<u16>,
+    },
+    Step {
+        #[serde(default = "one")]
+        count: u32,
+    },
+    RunFrames {
+        #[serde(default = "one")]
+        count: u32,
+    },
+    Press {
+        key: usize,
+    },
+    Release {
+        key: usize,
+    },
+    Peek {
+        addr: u16,
+        #[serde(default = "one")]
+        len: u32,
+    },
+    Poke {
+        addr: u16,
+        bytes: Vec<u8>,
+    },
+    Registers,
+    Screenshot,
+    ProfileStart,
+    Profile,
+    Quit,
+}
+
+// Either a single instruction or a whole frame
+type Advance = fn(&mut Chip8, &mut IO) -> Result<(), chip8::Error>;
+
+fn one() -> u32 {
+    1
+}
+
+pub struct Session {
+    chip8: Chip8,
+    screen: Framebuffer,
+    pad: Vec<bool>,
+    beep: bool,
+    freq: Option<f32>,
+    ipf: Option<u32>,
+}
+
+impl Session {
+    pub fn new(freq: Option<f32>, ipf: Option<u32>) -> Result<Self, Box<dyn std::error::Error>> {
+        let chip8 = Chip8::new(freq);
+        let (width, height) = chip8.get_screen_size();
+
+        Ok(Self {
+            screen: Framebuffer::new(width, height)?,
+            pad: vec![false; chip8.get_pad_map().len()],
+            beep: false,
+            chip8,
+            freq,
+            ipf,
+        })
+    }
+
+    // Loading replaces the machine, so that nothing from the previous ROM is left in memory
+    pub fn load(&mut self, rom: &[u8], seed: Option<u16>) -> Result<(), Box<dyn std::error::Error>> {
+        let mut chip8 = Chip8::new(self.freq);
+
+        if let Some(ipf) = self.ipf {
+            chip8.set_ipf(ipf)?;
+        }
+
+        chip8.load_rom(rom, seed)?;
+
+        self.chip8 = chip8;
+        self.screen.clear();
+        self.pad.fill(false);
+        self.beep = false;
+
+        Ok(())
+    }
+
+    // Returns the fields of a successful response
+    pub fn execute(&mut self, command: Command) -> Result<Map<String, Value>, Box<dyn std::error::Error>> {
+        let mut response = Map::new();
+
+        match command {
+            Command::Load { path, rom, seed } => {
+                let rom = match (path, rom) {
+                    (Some(path), None) => std::fs::read(path)?,
+                    (None, Some(rom)) => rom,
+                    _ => return Err("exactly one of 'path' and 'rom' is expected".into()),
+                };
+
+                self.load(&rom, seed)?;
+                response.insert("size".into(), json!(rom.len()));
+            }
+            Command::Step { count } => {
+                self.run(count, Chip8::step)?;
+
+                response.insert("beep".into(), json!(self.beep));
+            }
+            Command::RunFrames { count } => {
+                self.run(count, Chip8::frame)?;
+
+                response.insert("beep".into(), json!(self.beep));
+            }
+            Command::Press { key } => self.set_key(key, true)?,
+            Command::Release { key } => self.set_key(key, false)?,
+            Command::Peek { addr, len } => {
+                // Lengths are capped at the end of memory before allocating, addresses past it still fail
+                let len = (len as usize).min(MEMORY_SIZE.saturating_sub(addr.into()).max(1));
+                let mut bytes = vec![0; len];
+                self.chip8.view().read(addr, &mut bytes)?;
+                response.insert("bytes".into(), json!(bytes));
+            }
+            Command::Poke { addr, bytes } => self.chip8.edit()?.write(addr, &bytes)?,
+            Command::Registers => {
+                let view = self.chip8.view();
+
+                response.insert("v".into(), json!(view.get_v()));
+                response.insert("i".into(), json!(view.get_i()));
+                response.insert("pc".into(), json!(view.get_pc()));
+                response.insert("stack".into(), json!(view.get_stack()));
+                response.insert("dt".into(), json!(view.get_dt()));
+                response.insert("st".into(), json!(view.get_st()));
+                response.insert("cycles".into(), json!(view.get_cycles()));
+            }
+            Command::Screenshot => {
+                let (width, height) = self.chip8.get_screen_size();
+                let text = self.screen.to_text();
+
+                response.insert("width".into(), json!(width));
+                response.insert("height".into(), json!(height));
+                response.insert("rows".into(), json!(text.lines().collect::<Vec<_>>()));
+            }
+            Command::ProfileStart => self.chip8.record_profile(),
+            // Reports everything executed since profiling started, then stops profiling
+            Command::Profile => {
+                let profiler = self
+                    .chip8
+                    .take_profile()
+                    .ok_or("no profile in progress, 'profile_start' is expected first")?;
+                let (mut report, mut folded) = (Vec::new(), Vec::new());
+                profiler.write_report(&mut report)?;
+                profiler.write_folded(&mut folded)?;
+
+                response.insert("total".into(), json!(profiler.get_total()));
+                response.insert("report".into(), json!(String::from_utf8(report)?));
+                response.insert("folded".into(), json!(String::from_utf8(folded)?));
+            }
+            Command::Quit => {}
+        }
+
+        Ok(response)
+    }
+
+    // Edges are latched right away, so that taps shorter than a step are not lost
+    fn set_key(&mut self, key: usize, down: bool) -> Result<(), Box<dyn std::error::Error>> {
+        if key >= self.pad.len() {
+            return Err(format!("key {} is invalid, keys range from 0 to {}", key, self.pad.len() - 1).into());
+        }
+
+        if self.pad[key] != down {
+            self.chip8.key_event(if down {
+                KeyEvent::Press(key)
+            } else {
+                KeyEvent::Release(key)
+            })?;
+            self.pad[key] = down;
+        }
+
+        Ok(())
+    }
+
+    fn run(&mut self, count: u32, f: Advance) -> Result<(), chip8::Error> {
+        for _ in 0..count {
+            let mut io = IO {
+                screen: &mut self.screen,
+                pad: &self.pad,
+                audio: &mut self.beep,
+            };
+
+            f(&mut self.chip8, &mut io)?;
+        }
+
+        Ok(())
+    }
+}
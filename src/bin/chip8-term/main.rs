@@ -0,0 +1,51 @@
+use clap::Parser;
+
+use options::Options;
+use terminal::{Hotkey, Status, Terminal};
+
+use chip8::Chip8;
+
+mod options;
+mod terminal;
+
+fn main() {
+    try_main().unwrap_or_else(|err| {
+        eprintln!("Error: {}", err);
+        std::process::exit(1);
+    });
+}
+
+fn try_main() -> Result<(), Box<dyn std::error::Error>> {
+    let options = Options::parse_from(std::env::args());
+    let rom = std::fs::read(&options.rom)?;
+
+    let mut chip8 = Chip8::new(options.freq);
+
+    chip8.load_rom(&rom, options.seed)?;
+
+    if let Some(ipf) = options.ipf {
+        chip8.set_ipf(ipf)?;
+    }
+
+    // The terminal is restored when dropped, before any error is printed
+    let mut terminal = Terminal::new(chip8.get_screen_size(), chip8.get_pad_map(), &options)?;
+
+    terminal.run(|io, events, hotkeys| {
+        for hotkey in hotkeys {
+            match hotkey {
+                Hotkey::Pause if chip8.is_paused() => chip8.resume(),
+                Hotkey::Pause => chip8.pause(),
+            }
+        }
+
+        for event in events {
+            chip8.key_event(*event)?;
+        }
+
+        chip8.clock(io)?;
+
+        Ok(Status {
+            paused: chip8.is_paused(),
+        })
+    })
+}
@@ -0,0 +1,83 @@
+use std::fmt;
+
+use clap::{ArgEnum, Parser};
+use crossterm::style::Color;
+
+/// Another CHIP-8 toy emulator in Rust, in the terminal
+#[derive(Debug, Parser)]
+#[clap(name = "CHIP8")]
+pub struct Options {
+    /// Ring the terminal bell when the buzzer starts
+    #[clap(long)]
+    pub bell: bool,
+    /// Background color (format: #RRGGBB)
+    #[clap(long, parse(try_from_str = parse_color))]
+    pub bg: Option<Color>,
+    /// Foreground color (format: #RRGGBB)
+    #[clap(long, parse(try_from_str = parse_color))]
+    pub fg: Option<Color>,
+    /// CPU Frequency (in hertz)
+    #[clap(long)]
+    pub freq: Option<f32>,
+    /// Run exactly this many instructions per 60 Hz frame, instead of a CPU frequency
+    #[clap(long, conflicts_with = "freq")]
+    pub ipf: Option<u32>,
+    /// Time a key stays held without repeats, when the terminal does not report releases (in milliseconds)
+    #[clap(long, value_name = "MS")]
+    pub key_timeout: Option<u64>,
+    /// Characters used to draw pixels
+    #[clap(long, arg_enum, default_value = "half-block")]
+    pub render: Render,
+    /// CPU PRNG seed (in hexadecimal)
+    #[clap(long, parse(try_from_str = parse_seed))]
+    pub seed: Option<u16>,
+    /// Path to CHIP-8 ROM to run
+    pub rom: std::path::PathBuf,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ArgEnum)]
+pub enum Render {
+    /// 1x2 pixels per character
+    HalfBlock,
+    /// 2x4 pixels per character
+    Braille,
+}
+
+#[derive(Debug)]
+pub enum OptionError {
+    InvalidColor(String),
+    InvalidSeed(String),
+}
+
+fn parse_seed(src: &str) -> Result<u16, OptionError> {
+    src.strip_prefix("0x")
+        .and_then(|hex| u16::from_str_radix(hex, 16).ok())
+        .ok_or_else(|| OptionError::InvalidSeed(src.into()))
+}
+
+fn parse_color(src: &str) -> Result<Color, OptionError> {
+    src.strip_prefix('#')
+        .filter(|hex| hex.len() == 6)
+        .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+        .map(|hex| Color::Rgb {
+            r: ((hex >> 16) & 0xff) as u8,
+            g: ((hex >> 8) & 0xff) as u8,
+            b: (hex & 0xff) as u8,
+        })
+        .ok_or_else(|| OptionError::InvalidColor(src.into()))
+}
+
+impl fmt::Display for OptionError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Self::InvalidColor(color) => {
+                write!(f, "invalid color '{}', expected format is #RRGGBB", color)
+            }
+            Self::InvalidSeed(seed) => {
+                write!(f, "invalid seed '{}', expected format is 0xXXXX", seed)
+            }
+        }
+    }
+}
+
+impl std::error::Error for OptionError {}
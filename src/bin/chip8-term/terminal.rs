@@ -0,0 +1,192 @@
+use std::io::{Stdout, Write};
+use std::time::{Duration, Instant};
+
+use crossterm::event::{
+    self, Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers, KeyboardEnhancementFlags, PopKeyboardEnhancementFlags,
+    PushKeyboardEnhancementFlags,
+};
+use crossterm::style::{Color, Print};
+use crossterm::{cursor, execute, queue, terminal};
+
+use crate::options;
+
+mod keyboard;
+mod video;
+
+const TERMINAL_FRAMERATE: u32 = 60;
+const TERMINAL_BACKGROUND: Color = Color::Rgb {
+    r: 0x28,
+    g: 0x28,
+    b: 0x28,
+};
+const TERMINAL_FOREGROUND: Color = Color::Rgb {
+    r: 0xd5,
+    g: 0xc4,
+    b: 0xa1,
+};
+// Longer than the usual auto-repeat delays, up to 660 ms on X11, so held keys are not released before repeats start
+const TERMINAL_KEY_TIMEOUT: Duration = Duration::from_millis(750);
+
+pub struct Terminal {
+    out: Stdout,
+    video: video::VideoEngine,
+    keyboard: keyboard::KeyboardEngine,
+    hotkeys: Vec<Hotkey>,
+    enhanced: bool,
+    bell: bool,
+    beep: bool,
+}
+
+// Emulator state reported after each clock
+pub struct Status {
+    pub paused: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hotkey {
+    Pause,
+}
+
+impl Terminal {
+    pub fn new(dimensions: (usize, usize), keys: &[char], options: &options::Options) -> std::io::Result<Self> {
+        let timeout = options
+            .key_timeout
+            .map(Duration::from_millis)
+            .unwrap_or(TERMINAL_KEY_TIMEOUT);
+        let bg = options.bg.unwrap_or(TERMINAL_BACKGROUND);
+        let fg = options.fg.unwrap_or(TERMINAL_FOREGROUND);
+
+        let mut out = std::io::stdout();
+
+        terminal::enable_raw_mode()?;
+        execute!(out, terminal::EnterAlternateScreen, cursor::Hide)?;
+
+        // Terminals supporting the kitty keyboard protocol report key releases
+        let enhanced = terminal::supports_keyboard_enhancement().unwrap_or(false);
+
+        if enhanced {
+            execute!(
+                out,
+                PushKeyboardEnhancementFlags(
+                    KeyboardEnhancementFlags::DISAMBIGUATE_ESCAPE_CODES | KeyboardEnhancementFlags::REPORT_EVENT_TYPES
+                )
+            )?;
+        }
+
+        let mut keyboard = keyboard::KeyboardEngine::new(keys, timeout);
+        keyboard.set_releases(enhanced);
+
+        Ok(Self {
+            out,
+            video: video::VideoEngine::new(dimensions, options.render, bg, fg, terminal::size()?),
+            keyboard,
+            hotkeys: Vec::new(),
+            enhanced,
+            bell: options.bell,
+            beep: false,
+        })
+    }
+
+    pub fn get_io(&mut self) -> chip8::IO<'_> {
+        chip8::IO {
+            pad: self.keyboard.get_memory(),
+            screen: &mut self.video,
+            audio: &mut self.beep,
+        }
+    }
+
+    pub fn run<F>(&mut self, mut f: F) -> Result<(), Box<dyn std::error::Error>>
+    where
+        F: FnMut(&mut chip8::IO, &[chip8::KeyEvent], &[Hotkey]) -> Result<Status, Box<dyn std::error::Error>>,
+    {
+        let interval = Duration::from_secs(1) / TERMINAL_FRAMERATE;
+        let mut next = Instant::now();
+
+        loop {
+            next += interval;
+
+            if !self.process_events(next)? {
+                return Ok(());
+            }
+
+            let beep = self.beep;
+            let hotkeys = std::mem::take(&mut self.hotkeys);
+            let events = self.keyboard.take_events();
+            let status = f(&mut self.get_io(), &events, &hotkeys)?;
+
+            if self.bell && self.beep && !beep {
+                queue!(self.out, Print('\x07'))?;
+            }
+
+            let status = self.get_status(&status);
+            self.video.render(&mut self.out, &status)?;
+            self.out.flush()?;
+
+            // Frames that took too long are not caught up, emulation timing is handled by the clock
+            next = next.max(Instant::now());
+        }
+    }
+
+    fn get_status(&self, status: &Status) -> String {
+        let mut line = String::from("CHIP-8");
+
+        if status.paused {
+            line.push_str(" - paused");
+        }
+
+        if self.beep {
+            line.push_str(" - \u{266a} beep");
+        }
+
+        line.push_str(" (space: pause, esc: quit)");
+        line
+    }
+
+    // Waits for events until the deadline, returns false when quitting
+    fn process_events(&mut self, deadline: Instant) -> std::io::Result<bool> {
+        loop {
+            let now = Instant::now();
+            self.keyboard.expire(now);
+
+            if !event::poll(deadline.saturating_duration_since(now))? {
+                return Ok(true);
+            }
+
+            match event::read()? {
+                Event::Resize(cols, rows) => self.video.resize((cols, rows)),
+                Event::Key(key) if !self.process_key(key) => return Ok(false),
+                _ => {}
+            }
+        }
+    }
+
+    fn process_key(&mut self, key: KeyEvent) -> bool {
+        match (key.code, key.kind) {
+            // Quit application
+            (KeyCode::Esc, KeyEventKind::Press) => return false,
+            (KeyCode::Char('c'), KeyEventKind::Press) if key.modifiers.contains(KeyModifiers::CONTROL) => return false,
+            // Emulation control
+            (KeyCode::Char(' '), KeyEventKind::Press) => self.hotkeys.push(Hotkey::Pause),
+            // Key down, repeats refresh the release timeout
+            (KeyCode::Char(c), KeyEventKind::Press | KeyEventKind::Repeat) => {
+                self.keyboard.key_down(c, Instant::now());
+            }
+            // Key up
+            (KeyCode::Char(c), KeyEventKind::Release) => self.keyboard.key_up(c),
+            _ => {}
+        }
+
+        true
+    }
+}
+
+impl Drop for Terminal {
+    fn drop(&mut self) {
+        if self.enhanced {
+            let _ = execute!(self.out, PopKeyboardEnhancementFlags);
+        }
+
+        let _ = execute!(self.out, terminal::LeaveAlternateScreen, cursor::Show);
+        let _ = terminal::disable_raw_mode();
+    }
+}
@@ -0,0 +1,178 @@
+use std::time::{Duration, Instant};
+
+// Auto-repeat usually starts after 250 to 660 ms, then repeats every 30 to 50 ms
+const KEY_TIMEOUT_REPEAT: Duration = Duration::from_millis(100);
+
+// Terminals without release reports only send presses, so keys are released after a timeout without repeats
+pub struct KeyboardEngine {
+    keys: Vec<char>,
+    buffer: Vec<bool>,
+    deadlines: Vec<Option<Instant>>,
+    events: Vec<chip8::KeyEvent>,
+    timeout: Duration,
+    releases: bool,
+}
+
+impl KeyboardEngine {
+    pub fn new(keys: &[char], timeout: Duration) -> Self {
+        Self {
+            keys: keys.iter().map(|c| c.to_ascii_lowercase()).collect(),
+            buffer: vec![false; keys.len()],
+            deadlines: vec![None; keys.len()],
+            events: Vec::new(),
+            timeout,
+            releases: false,
+        }
+    }
+
+    // Once the terminal reports releases, timeouts are no longer needed
+    pub fn set_releases(&mut self, releases: bool) {
+        self.releases = releases;
+    }
+
+    pub fn key_down(&mut self, c: char, now: Instant) {
+        if let Some(idx) = self.get_index(c) {
+            if !self.buffer[idx] {
+                self.events.push(chip8::KeyEvent::Press(idx));
+                self.deadlines[idx] = Some(now + self.timeout);
+            } else {
+                self.deadlines[idx] = Some(now + KEY_TIMEOUT_REPEAT.min(self.timeout));
+            }
+
+            if self.releases {
+                self.deadlines[idx] = None;
+            }
+
+            self.buffer[idx] = true;
+        }
+    }
+
+    pub fn key_up(&mut self, c: char) {
+        if let Some(idx) = self.get_index(c) {
+            self.release(idx);
+        }
+    }
+
+    pub fn expire(&mut self, now: Instant) {
+        for idx in 0..self.keys.len() {
+            if self.deadlines[idx].is_some_and(|deadline| deadline <= now) {
+                self.release(idx);
+            }
+        }
+    }
+
+    pub fn get_memory(&self) -> &[bool] {
+        &self.buffer
+    }
+
+    pub fn take_events(&mut self) -> Vec<chip8::KeyEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    fn release(&mut self, idx: usize) {
+        if self.buffer[idx] {
+            self.events.push(chip8::KeyEvent::Release(idx));
+        }
+        self.buffer[idx] = false;
+        self.deadlines[idx] = None;
+    }
+
+    fn get_index(&self, c: char) -> Option<usize> {
+        self.keys.iter().position(|key| *key == c.to_ascii_lowercase())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chip8::KeyEvent;
+
+    use super::*;
+
+    const KEYS: [char; 3] = ['x', '1', 'q'];
+    const TIMEOUT: Duration = Duration::from_millis(750);
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn tap_is_released_after_timeout() {
+        let start = Instant::now();
+        let mut keyboard = KeyboardEngine::new(&KEYS, TIMEOUT);
+
+        keyboard.key_down('1', start);
+        assert_eq!(keyboard.get_memory(), [false, true, false]);
+
+        keyboard.expire(start + TIMEOUT - ms(1));
+        assert_eq!(keyboard.get_memory(), [false, true, false]);
+
+        keyboard.expire(start + TIMEOUT);
+        assert_eq!(keyboard.get_memory(), [false, false, false]);
+        assert_eq!(keyboard.take_events(), [KeyEvent::Press(1), KeyEvent::Release(1)]);
+    }
+
+    #[test]
+    fn held_key_survives_the_first_repeat_delay() {
+        let start = Instant::now();
+        let mut keyboard = KeyboardEngine::new(&KEYS, TIMEOUT);
+
+        // X11 starts repeating after 660 ms, then repeats every 40 ms
+        keyboard.key_down('x', start);
+        keyboard.expire(start + ms(660));
+
+        for t in (660..=1020).step_by(40) {
+            keyboard.key_down('x', start + ms(t));
+            keyboard.expire(start + ms(t + 39));
+            assert!(keyboard.get_memory()[0], "released at {} ms", t + 39);
+        }
+
+        assert_eq!(keyboard.take_events(), [KeyEvent::Press(0)]);
+
+        // Once repeats stop, the key is released after the shorter repeat timeout
+        keyboard.expire(start + ms(1020 + 99));
+        assert!(keyboard.get_memory()[0]);
+        keyboard.expire(start + ms(1020 + 100));
+        assert!(!keyboard.get_memory()[0]);
+        assert_eq!(keyboard.take_events(), [KeyEvent::Release(0)]);
+    }
+
+    #[test]
+    fn short_timeouts_also_bound_repeats() {
+        let start = Instant::now();
+        let mut keyboard = KeyboardEngine::new(&KEYS, ms(50));
+
+        keyboard.key_down('q', start);
+        keyboard.key_down('q', start + ms(40));
+        keyboard.expire(start + ms(89));
+        assert!(keyboard.get_memory()[2]);
+        keyboard.expire(start + ms(90));
+        assert!(!keyboard.get_memory()[2]);
+    }
+
+    #[test]
+    fn reported_releases_disable_timeouts() {
+        let start = Instant::now();
+        let mut keyboard = KeyboardEngine::new(&KEYS, TIMEOUT);
+        keyboard.set_releases(true);
+
+        keyboard.key_down('X', start);
+        keyboard.expire(start + ms(10_000));
+        assert!(keyboard.get_memory()[0]);
+
+        keyboard.key_up('x');
+        keyboard.key_up('x');
+        assert!(!keyboard.get_memory()[0]);
+        assert_eq!(keyboard.take_events(), [KeyEvent::Press(0), KeyEvent::Release(0)]);
+    }
+
+    #[test]
+    fn unmapped_keys_are_ignored() {
+        let start = Instant::now();
+        let mut keyboard = KeyboardEngine::new(&KEYS, TIMEOUT);
+
+        keyboard.key_down('z', start);
+        keyboard.key_up('z');
+        assert_eq!(keyboard.get_memory(), [false; 3]);
+        assert!(keyboard.take_events().is_empty());
+    }
+}
@@ -0,0 +1,162 @@
+use std::io::Write;
+
+use chip8::Screen;
+use crossterm::cursor::MoveTo;
+use crossterm::style::{Color, Print, SetColors};
+use crossterm::terminal::{Clear, ClearType};
+use crossterm::{queue, style};
+
+use crate::options::Render;
+
+// Braille dot bits, indexed by row then column of the 2x4 cell
+const BRAILLE_DOTS: [[u32; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+
+pub struct VideoEngine {
+    buffer: chip8::Framebuffer,
+    render: Render,
+    bg: Color,
+    fg: Color,
+    size: (u16, u16),
+    invalidated: bool,
+}
+
+impl VideoEngine {
+    pub fn new(dimensions: (usize, usize), render: Render, bg: Color, fg: Color, size: (u16, u16)) -> Self {
+        Self {
+            buffer: chip8::Framebuffer::new(dimensions.0, dimensions.1).unwrap_or_default(),
+            render,
+            bg,
+            fg,
+            size,
+            invalidated: true,
+        }
+    }
+
+    // Terminal size in characters, the screen is fully redrawn after a resize
+    pub fn resize(&mut self, size: (u16, u16)) {
+        self.size = size;
+        self.invalidated = true;
+    }
+
+    // Characters needed for the screen, plus the status line
+    pub fn get_cells(&self) -> (u16, u16) {
+        let (width, height) = self.buffer.size();
+
+        let (cols, rows) = match self.render {
+            Render::HalfBlock => (width, height.div_ceil(2)),
+            Render::Braille => (width.div_ceil(2), height.div_ceil(4)),
+        };
+
+        (cols as u16, rows as u16 + 1)
+    }
+
+    pub fn render<W: Write>(&mut self, out: &mut W, status: &str) -> std::io::Result<()> {
+        let (cols, rows) = self.get_cells();
+        let changed = self.buffer.take_damage().is_some();
+        let invalidated = std::mem::take(&mut self.invalidated);
+
+        if invalidated {
+            queue!(out, style::ResetColor, Clear(ClearType::All))?;
+        }
+
+        if self.size.0 < cols || self.size.1 < rows {
+            return queue!(
+                out,
+                MoveTo(0, 0),
+                Print(format!("Terminal too small, {}x{} characters are needed", cols, rows))
+            );
+        }
+
+        if changed || invalidated {
+            for row in 0..(rows - 1) {
+                queue!(out, MoveTo(0, row))?;
+
+                match self.render {
+                    Render::HalfBlock => self.render_half_block(out, row as usize)?,
+                    Render::Braille => self.render_braille(out, row as usize)?,
+                }
+            }
+        }
+
+        queue!(
+            out,
+            MoveTo(0, rows - 1),
+            style::ResetColor,
+            Clear(ClearType::CurrentLine),
+            Print(status)
+        )
+    }
+
+    // Upper pixels use the foreground color of the half block, lower ones the background color
+    fn render_half_block<W: Write>(&self, out: &mut W, row: usize) -> std::io::Result<()> {
+        let (width, height) = self.buffer.size();
+        let color = |x: usize, y: usize| match y < height && self.buffer.get_pixel(x, y) {
+            true => self.fg,
+            false => self.bg,
+        };
+
+        let mut current = None;
+
+        for x in 0..width {
+            let colors = style::Colors::new(color(x, 2 * row), color(x, 2 * row + 1));
+
+            if current != Some(colors) {
+                queue!(out, SetColors(colors))?;
+                current = Some(colors);
+            }
+
+            queue!(out, Print('▀'))?;
+        }
+
+        Ok(())
+    }
+
+    fn render_braille<W: Write>(&self, out: &mut W, row: usize) -> std::io::Result<()> {
+        let (width, height) = self.buffer.size();
+        let mut line = String::with_capacity(width.div_ceil(2) * 3);
+
+        for col in 0..width.div_ceil(2) {
+            let mut dots = 0;
+
+            for (dy, bits) in BRAILLE_DOTS.iter().enumerate() {
+                for (dx, bit) in bits.iter().enumerate() {
+                    let (x, y) = (2 * col + dx, 4 * row + dy);
+
+                    if x < width && y < height && self.buffer.get_pixel(x, y) {
+                        dots |= bit;
+                    }
+                }
+            }
+
+            line.push(char::from_u32(0x2800 + dots).unwrap_or(' '));
+        }
+
+        queue!(out, SetColors(style::Colors::new(self.fg, self.bg)), Print(line))
+    }
+}
+
+impl chip8::Screen for VideoEngine {
+    fn get_width(&self) -> usize {
+        self.buffer.get_width()
+    }
+
+    fn get_height(&self) -> usize {
+        self.buffer.get_height()
+    }
+
+    fn get_pixel(&self, x: usize, y: usize) -> bool {
+        self.buffer.get_pixel(x, y)
+    }
+
+    fn set_pixel(&mut self, x: usize, y: usize, on: bool) {
+        self.buffer.set_pixel(x, y, on)
+    }
+
+    fn clear(&mut self) {
+        self.buffer.clear()
+    }
+
+    fn draw(&mut self, x: u8, y: u8, byte: u8) -> bool {
+        self.buffer.draw(x, y, byte)
+    }
+}
@@ -1,9 +1,11 @@
 use std::error;
 use std::fmt;
+use std::net::SocketAddr;
 
 #[derive(Debug)]
 pub enum Error {
     InvalidFps(u32),
+    PublicAddress(SocketAddr),
     ScaleOverflow(usize, u8),
     ScreenTooLarge((usize, usize)),
     Sdl(SdlError),
@@ -21,6 +23,7 @@ impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
             Self::InvalidFps(fps) => write!(f, "framerate of {} fps is invalid", fps),
+            Self::PublicAddress(addr) => write!(f, "address {} is not loopback, --listen-public allows it", addr),
             Self::ScaleOverflow(x, scale) => write!(f, "cannot scale size of {} by {} times", x, scale),
             Self::ScreenTooLarge(dim) => write!(f, "screen size of {}x{} cannot be created", dim.0, dim.1),
             Self::Sdl(err) => match err {
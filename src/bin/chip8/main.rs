@@ -1,14 +1,36 @@
+use std::net::{Ipv4Addr, UdpSocket};
+use std::time::{Duration, Instant};
+
 use clap::Parser;
 
-use options::Options;
-use window::Window;
+use options::{AnalyzeFormat, AnalyzeOptions, Command, Options};
+use remote::Remote;
+use window::{Hotkey, Status, Window};
 
+use chip8::Analysis;
+use chip8::AudioRenderer;
+use chip8::CatchUp;
 use chip8::Chip8;
+use chip8::Movie;
+use chip8::Netplay;
+use chip8::Pcg;
+use chip8::Vip;
+use chip8::WaitMode;
+use chip8::MEMORY_SIZE;
 
 mod error;
 mod options;
+mod remote;
 mod window;
 
+// Speed multipliers reachable from hotkeys
+const SPEED_MIN: f64 = 0.125;
+const SPEED_MAX: f64 = 8.0;
+const SPEED_FAST_FORWARD: f64 = 4.0;
+
+// Netplay emulates one frame per timer tick
+const NETPLAY_FRAME: Duration = Duration::from_nanos(1_000_000_000 / 60);
+
 fn main() {
     try_main().unwrap_or_else(|err| {
         eprintln!("Error: {}", err);
@@ -18,17 +40,216 @@ fn main() {
 
 fn try_main() -> Result<(), Box<dyn std::error::Error>> {
     let options = Options::parse_from(std::env::args());
+
+    match &options.command {
+        Some(Command::Analyze(analyze_options)) => analyze(analyze_options),
+        None => run(&options),
+    }
+}
+
+fn analyze(options: &AnalyzeOptions) -> Result<(), Box<dyn std::error::Error>> {
     let rom = std::fs::read(&options.rom)?;
+    let analysis = Analysis::new(&rom);
+
+    let output: Box<dyn std::io::Write> = match &options.output {
+        Some(path) => Box::new(std::io::BufWriter::new(std::fs::File::create(path)?)),
+        None => Box::new(std::io::stdout().lock()),
+    };
+
+    match options.format {
+        AnalyzeFormat::Dot => analysis.write_dot(output)?,
+        AnalyzeFormat::Json => analysis.write_json(output)?,
+    }
+
+    Ok(())
+}
+
+fn run(options: &Options) -> Result<(), Box<dyn std::error::Error>> {
+    let rom = std::fs::read(options.rom.as_ref().ok_or("missing ROM path")?)?;
 
     let mut chip8 = Chip8::new(options.freq);
-    let mut window = Window::new(chip8.get_screen_size(), chip8.get_pad_map(), &options)?;
+
+    // Listen before opening the window, so that address errors are reported right away
+    let mut remote = match options.listen {
+        Some(addr) if !addr.ip().is_loopback() && !options.listen_public => {
+            return Err(error::Error::PublicAddress(addr).into());
+        }
+        Some(addr) => Some(Remote::listen(addr, chip8.get_pad_map().len())?),
+        None => None,
+    };
+
+    let mut window = Window::new(chip8.get_screen_size(), chip8.get_pad_map(), options)?;
 
     chip8.load_rom(&rom, options.seed)?;
 
-    window.run(|io| {
-        chip8.clock(io)?;
-        Ok(())
-    })?;
+    match options.rng {
+        Some(options::Rng::Lfsr) | None => {}
+        Some(options::Rng::Pcg) => chip8.set_rng(Pcg::new()),
+        Some(options::Rng::Vip) => {
+            let path = options
+                .vip_interpreter
+                .as_ref()
+                .ok_or("--rng vip requires --vip-interpreter")?;
+            let interpreter = std::fs::read(path)?;
+            let interpreter = interpreter
+                .as_slice()
+                .try_into()
+                .map_err(|_| format!("VIP interpreter dumps are 512 bytes, got {}", interpreter.len()))?;
 
-    Ok(())
+            chip8.set_rng(Vip::new(interpreter));
+        }
+    }
+
+    if let Some(ipf) = options.ipf {
+        chip8.set_ipf(ipf)?;
+    }
+
+    let mut speed = options.speed.unwrap_or(1.0);
+    let mut fast_forward = false;
+
+    chip8.set_speed(speed)?;
+
+    chip8.set_catch_up(
+        match options.catch_up {
+            options::CatchUp::Drop => CatchUp::Drop,
+            options::CatchUp::SlowDown => CatchUp::SlowDown,
+            options::CatchUp::SkipFrames => CatchUp::SkipFrames,
+        },
+        None,
+    );
+
+    if let Some(mode) = options.wait_mode {
+        chip8.set_wait_mode(match mode {
+            options::WaitMode::Press => WaitMode::Press,
+            options::WaitMode::Release => WaitMode::Release,
+            options::WaitMode::Vip => WaitMode::Vip,
+        });
+    }
+
+    if options.record_audio.is_some() {
+        chip8.record_audio();
+    }
+
+    if options.coverage.is_some() || options.coverage_dump.is_some() {
+        chip8.record_coverage();
+    }
+
+    if options.profile.is_some() || options.profile_folded.is_some() {
+        chip8.record_profile();
+    }
+
+    if options.report_smc {
+        chip8.record_smc();
+    }
+
+    if let Some(path) = &options.play {
+        chip8.play_movie(Movie::from_bytes(&std::fs::read(path)?)?)?;
+    } else if options.record.is_some() {
+        chip8.record_movie()?;
+    }
+
+    let mut netplay = match options.netplay {
+        Some(peer) => {
+            let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, options.netplay_port.unwrap_or(peer.port())))?;
+            Some(Netplay::new(socket, peer, options.input_delay)?)
+        }
+        None => None,
+    };
+    let mut next_frame = Instant::now();
+    let mut waiting = false;
+
+    let result = window.run(|io, events, hotkeys| {
+        // Lockstep sessions cannot be paused or sped up, and only exchange pad snapshots, hotkeys and events are ignored
+        if let Some(netplay) = &mut netplay {
+            let now = Instant::now();
+
+            if now >= next_frame {
+                waiting = !netplay.frame(&mut chip8, io)?;
+                // The schedule restarts from now when falling behind, missed ticks are not caught up
+                next_frame = (next_frame + NETPLAY_FRAME).max(now);
+            }
+
+            return Ok(Status {
+                behind: false,
+                dropped_cycles: 0,
+                paused: false,
+                speed: 1.0,
+                waiting,
+            });
+        }
+
+        for hotkey in hotkeys {
+            match hotkey {
+                Hotkey::Pause if chip8.is_paused() => chip8.resume(),
+                Hotkey::Pause => chip8.pause(),
+                Hotkey::StepFrame => chip8.step_frame(io)?,
+                Hotkey::FastForward(held) => fast_forward = *held,
+                Hotkey::SpeedUp => speed = (speed * 2.0).min(SPEED_MAX),
+                Hotkey::SpeedDown => speed = (speed / 2.0).max(SPEED_MIN),
+            }
+        }
+
+        chip8.set_speed(if fast_forward { SPEED_FAST_FORWARD } else { speed })?;
+
+        for event in events {
+            chip8.key_event(*event)?;
+        }
+
+        match &mut remote {
+            Some(remote) => remote.clock(&mut chip8, io)?,
+            None => chip8.clock(io)?,
+        }
+
+        Ok(Status {
+            behind: chip8.is_behind(),
+            dropped_cycles: chip8.get_dropped_cycles(),
+            paused: chip8.is_paused(),
+            speed: chip8.get_speed(),
+            waiting: false,
+        })
+    });
+
+    // Replays stop at the end of the movie, which is not a failure
+    let result = match result {
+        Err(err) if matches!(err.downcast_ref(), Some(chip8::Error::MovieFinished(_))) => Ok(()),
+        result => result,
+    };
+
+    // Recordings are saved even if emulation failed, so that the failure can be reproduced
+    if let (Some(path), Some(movie)) = (&options.record, chip8.take_movie()) {
+        std::fs::write(path, movie.to_bytes())?;
+    }
+
+    if let (Some(path), Some(timeline)) = (&options.record_audio, chip8.take_audio()) {
+        let wav = std::io::BufWriter::new(std::fs::File::create(path)?);
+        AudioRenderer::new(None)?.write_wav(&timeline, wav)?;
+    }
+
+    if let Some(coverage) = chip8.take_coverage() {
+        if let Some(path) = &options.coverage {
+            coverage.write_json(std::io::BufWriter::new(std::fs::File::create(path)?))?;
+        }
+
+        if let Some(path) = &options.coverage_dump {
+            let mut memory = [0x00; MEMORY_SIZE];
+            chip8.view().read(0x0000, &mut memory)?;
+            coverage.write_hexdump(&memory, std::io::BufWriter::new(std::fs::File::create(path)?))?;
+        }
+    }
+
+    if let Some(profiler) = chip8.take_profile() {
+        if let Some(path) = &options.profile {
+            profiler.write_report(std::io::BufWriter::new(std::fs::File::create(path)?))?;
+        }
+
+        if let Some(path) = &options.profile_folded {
+            profiler.write_folded(std::io::BufWriter::new(std::fs::File::create(path)?))?;
+        }
+    }
+
+    if let Some(smc) = chip8.take_smc() {
+        smc.write_report(std::io::stderr().lock())?;
+    }
+
+    result
 }
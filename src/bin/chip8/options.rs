@@ -1,15 +1,31 @@
 use std::fmt;
+use std::net::{AddrParseError, Ipv4Addr, SocketAddr};
 
-use clap::Parser;
+use clap::{ArgEnum, Args, Parser, Subcommand};
 use sdl2::pixels::Color;
 
 /// Another CHIP-8 toy emulator in Rust
 #[derive(Debug, Parser)]
-#[clap(name = "CHIP8")]
+#[clap(
+    name = "CHIP8",
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true
+)]
 pub struct Options {
+    #[clap(subcommand)]
+    pub command: Option<Command>,
     /// Window background color (format: #RRGGBB)
     #[clap(long, parse(try_from_str = parse_color))]
     pub bg: Option<Color>,
+    /// Policy when emulation falls behind real time
+    #[clap(long, arg_enum, default_value = "drop")]
+    pub catch_up: CatchUp,
+    /// Write a JSON coverage report of executed code and accessed data
+    #[clap(long, value_name = "JSON")]
+    pub coverage: Option<std::path::PathBuf>,
+    /// Write a hex dump of memory annotated with coverage
+    #[clap(long, value_name = "TXT")]
+    pub coverage_dump: Option<std::path::PathBuf>,
     /// Window foreground color (format: #RRGGBB)
     #[clap(long, parse(try_from_str = parse_color))]
     pub fg: Option<Color>,
@@ -19,22 +35,132 @@ pub struct Options {
     /// CPU Frequency (in hertz)
     #[clap(long)]
     pub freq: Option<f32>,
+    /// Keypad input delay during netplay (in frames)
+    #[clap(long, value_name = "FRAMES", requires = "netplay")]
+    pub input_delay: Option<u32>,
+    /// Run exactly this many instructions per 60 Hz frame, instead of a CPU frequency
+    #[clap(long, conflicts_with = "freq")]
+    pub ipf: Option<u32>,
+    /// Accept remote-control commands over TCP (format: PORT or ADDRESS:PORT, loopback by default)
+    #[clap(long, value_name = "ADDR", parse(try_from_str = parse_listen))]
+    pub listen: Option<SocketAddr>,
+    /// Allow --listen on addresses other than loopback
+    #[clap(long, requires = "listen")]
+    pub listen_public: bool,
+    /// Play with a peer over UDP, both sharing the keypad (format: ADDRESS:PORT)
+    #[clap(long, value_name = "PEER", conflicts_with_all = &["listen", "play", "record"])]
+    pub netplay: Option<SocketAddr>,
+    /// Local UDP port for netplay (defaults to the peer port)
+    #[clap(long, value_name = "PORT", requires = "netplay")]
+    pub netplay_port: Option<u16>,
+    /// Write a profile of executed instructions and subroutines
+    #[clap(long, value_name = "TXT")]
+    pub profile: Option<std::path::PathBuf>,
+    /// Write profiled call stacks in folded format, for flamegraph tools
+    #[clap(long, value_name = "FOLDED")]
+    pub profile_folded: Option<std::path::PathBuf>,
+    /// Replay keypad input from a movie file
+    #[clap(long, value_name = "MOVIE", conflicts_with = "record")]
+    pub play: Option<std::path::PathBuf>,
+    /// Record keypad input to a movie file
+    #[clap(long, value_name = "MOVIE")]
+    pub record: Option<std::path::PathBuf>,
+    /// Record the buzzer output to a WAV file
+    #[clap(long, value_name = "WAV")]
+    pub record_audio: Option<std::path::PathBuf>,
+    /// Report self-modifying code on exit
+    #[clap(long)]
+    pub report_smc: bool,
+    /// Random number generator used by CXKK
+    #[clap(long, arg_enum)]
+    pub rng: Option<Rng>,
     /// Window scale
     #[clap(long, possible_values = [ "1", "2", "4", "8", "16" ])]
     pub scale: Option<u8>,
+    /// Emulation speed multiplier
+    #[clap(long)]
+    pub speed: Option<f64>,
     /// CPU PRNG seed (in hexadecimal)
     #[clap(long, parse(try_from_str = parse_seed))]
     pub seed: Option<u16>,
+    /// Dump of the 512-byte COSMAC VIP interpreter, read by --rng vip
+    #[clap(long, value_name = "DUMP", required_if_eq("rng", "vip"))]
+    pub vip_interpreter: Option<std::path::PathBuf>,
+    /// Condition completing the FX0A key wait instruction
+    #[clap(long, arg_enum)]
+    pub wait_mode: Option<WaitMode>,
     /// Path to CHIP-8 ROM to run
+    #[clap(required = true)]
+    pub rom: Option<std::path::PathBuf>,
+}
+
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// Statically analyze the control flow of a ROM
+    Analyze(AnalyzeOptions),
+}
+
+#[derive(Debug, Args)]
+pub struct AnalyzeOptions {
+    /// Output format
+    #[clap(long, arg_enum, default_value = "dot")]
+    pub format: AnalyzeFormat,
+    /// Output file (defaults to standard output)
+    #[clap(long, short)]
+    pub output: Option<std::path::PathBuf>,
+    /// Path to CHIP-8 ROM to analyze
     pub rom: std::path::PathBuf,
 }
 
+#[derive(Debug, Clone, Copy, ArgEnum)]
+pub enum AnalyzeFormat {
+    Dot,
+    Json,
+}
+
+#[derive(Debug, Clone, Copy, ArgEnum)]
+pub enum CatchUp {
+    /// Skip missed time, CPU cycles and timers alike
+    Drop,
+    /// Run the whole machine slower
+    SlowDown,
+    /// Catch up without presenting frames
+    SkipFrames,
+}
+
+#[derive(Debug, Clone, Copy, ArgEnum)]
+pub enum Rng {
+    /// 16-bit LFSR
+    Lfsr,
+    /// PCG32
+    Pcg,
+    /// COSMAC VIP interpreter routine, requires --vip-interpreter
+    Vip,
+}
+
+#[derive(Debug, Clone, Copy, ArgEnum)]
+pub enum WaitMode {
+    /// Key pressed
+    Press,
+    /// Key pressed then released
+    Release,
+    /// Key pressed then released, beeping while held
+    Vip,
+}
+
 #[derive(Debug)]
 pub enum OptionError {
     InvalidColor(String),
     InvalidSeed(String),
 }
 
+// A bare port binds to loopback
+fn parse_listen(src: &str) -> Result<SocketAddr, AddrParseError> {
+    src.parse::<u16>()
+        .map(|port| SocketAddr::from((Ipv4Addr::LOCALHOST, port)))
+        .or_else(|_| src.parse())
+}
+
 fn parse_seed(src: &str) -> Result<u16, OptionError> {
     src.strip_prefix("0x")
         .and_then(|hex| u16::from_str_radix(hex, 16).ok())
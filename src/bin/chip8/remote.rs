@@ -0,0 +1,267 @@
+use std::fmt::Write as _;
+use std::io::{BufRead, BufReader, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::thread;
+
+use chip8::{Chip8, KeyEvent, Screen, IO, STATE_SIZE};
+
+// Commands are parsed by connection threads, the machine is only touched by the emulation thread
+#[derive(Debug)]
+enum Command {
+    Pause,
+    Resume,
+    Press(usize),
+    Release(usize),
+    Peek(u16, u16),
+    Screenshot,
+    Save,
+    Load(Vec<u8>),
+}
+
+struct Request {
+    command: Command,
+    reply: Sender<String>,
+}
+
+// Line-based control server, one command per line and one "ok ..." or "error ..." response per command
+pub struct Remote {
+    requests: Receiver<Request>,
+    pad: Vec<bool>,
+}
+
+impl Remote {
+    pub fn listen(addr: SocketAddr, keys: usize) -> std::io::Result<Self> {
+        let listener = TcpListener::bind(addr)?;
+        let (sender, requests) = mpsc::channel();
+
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let sender = sender.clone();
+                thread::spawn(move || Self::serve(stream, sender, keys));
+            }
+        });
+
+        Ok(Self {
+            requests,
+            pad: vec![false; keys],
+        })
+    }
+
+    // Handles pending requests, then clocks the machine with injected keys merged into host input
+    pub fn clock(&mut self, chip8: &mut Chip8, io: &mut IO) -> Result<(), chip8::Error> {
+        while let Ok(request) = self.requests.try_recv() {
+            let response = match self.execute(chip8, &mut *io.screen, request.command) {
+                Ok(response) => format!("ok{}", response),
+                Err(err) => format!("error {}", err),
+            };
+
+            // Clients may have disconnected in the meantime
+            let _ = request.reply.send(response);
+        }
+
+        let pad: Vec<bool> = io
+            .pad
+            .iter()
+            .zip(&self.pad)
+            .map(|(host, remote)| *host || *remote)
+            .collect();
+
+        chip8.clock(&mut IO {
+            screen: &mut *io.screen,
+            pad: &pad,
+            audio: &mut *io.audio,
+        })
+    }
+
+    fn execute(
+        &mut self,
+        chip8: &mut Chip8,
+        screen: &mut dyn Screen,
+        command: Command,
+    ) -> Result<String, chip8::Error> {
+        let mut response = String::new();
+
+        match command {
+            Command::Pause => chip8.pause(),
+            Command::Resume => chip8.resume(),
+            Command::Press(key) => self.set_key(chip8, key, true)?,
+            Command::Release(key) => self.set_key(chip8, key, false)?,
+            Command::Peek(addr, len) => {
+                let mut bytes = vec![0x00; len as usize];
+                chip8.view().read(addr, &mut bytes)?;
+                bytes.iter().for_each(|byte| write!(response, " {:02x}", byte).unwrap());
+            }
+            // Rows are hex words on the same line, the leftmost pixel being the most significant bit
+            Command::Screenshot => {
+                let (width, height) = screen.size();
+                write!(response, " {} {}", width, height).unwrap();
+
+                for y in 0..height {
+                    response.push(' ');
+
+                    for x in (0..width).step_by(4) {
+                        let nibble =
+                            (x..x + 4).fold(0, |nibble, x| nibble << 1 | (x < width && screen.get_pixel(x, y)) as u8);
+                        write!(response, "{:x}", nibble).unwrap();
+                    }
+                }
+            }
+            Command::Save => {
+                let mut state = [0x00; STATE_SIZE];
+                chip8.save_state(screen, &mut state)?;
+                response.push(' ');
+                state.iter().for_each(|byte| write!(response, "{:02x}", byte).unwrap());
+            }
+            Command::Load(state) => chip8.load_state(screen, &state)?,
+        }
+
+        Ok(response)
+    }
+
+    fn set_key(&mut self, chip8: &mut Chip8, key: usize, down: bool) -> Result<(), chip8::Error> {
+        if self.pad[key] != down {
+            chip8.key_event(if down {
+                KeyEvent::Press(key)
+            } else {
+                KeyEvent::Release(key)
+            })?;
+            self.pad[key] = down;
+        }
+
+        Ok(())
+    }
+
+    fn serve(stream: TcpStream, sender: Sender<Request>, keys: usize) -> std::io::Result<()> {
+        let mut writer = stream.try_clone()?;
+        let (reply, replies) = mpsc::channel();
+
+        for line in BufReader::new(stream).lines() {
+            let line = line?;
+
+            let response = match Self::parse(&line, keys) {
+                Ok(None) => continue,
+                Ok(Some(command)) => {
+                    let request = Request {
+                        command,
+                        reply: reply.clone(),
+                    };
+
+                    // Both ends are gone once emulation stopped
+                    if sender.send(request).is_err() {
+                        return Ok(());
+                    }
+
+                    match replies.recv() {
+                        Ok(response) => response,
+                        Err(_) => return Ok(()),
+                    }
+                }
+                Err(err) => format!("error {}", err),
+            };
+
+            writeln!(writer, "{}", response)?;
+        }
+
+        Ok(())
+    }
+
+    // Numbers are hexadecimal, as in a debugger
+    fn parse(line: &str, keys: usize) -> Result<Option<Command>, String> {
+        let words: Vec<&str> = line.split_whitespace().collect();
+
+        // Signs are accepted by from_str_radix, so digits are checked first
+        let digits = |word: &str| word.bytes().all(|b| b.is_ascii_hexdigit());
+        let hex = |word: &str| {
+            Some(word)
+                .filter(|word| digits(word))
+                .and_then(|word| u16::from_str_radix(word, 16).ok())
+                .ok_or_else(|| format!("invalid number '{}'", word))
+        };
+        let key = |word: &str| match hex(word)? as usize {
+            key if key < keys => Ok(key),
+            _ => Err(format!("invalid key '{}'", word)),
+        };
+
+        let command = match words.as_slice() {
+            [] => return Ok(None),
+            ["pause"] => Command::Pause,
+            ["resume"] => Command::Resume,
+            ["press", k] => Command::Press(key(k)?),
+            ["release", k] => Command::Release(key(k)?),
+            ["peek", addr] => Command::Peek(hex(addr)?, 1),
+            ["peek", addr, len] => Command::Peek(hex(addr)?, hex(len)?),
+            ["screenshot"] => Command::Screenshot,
+            ["save"] => Command::Save,
+            ["load", state] => Command::Load(
+                (0..state.len())
+                    .step_by(2)
+                    .map(|i| state.get(i..i + 2).filter(|byte| digits(byte)))
+                    .map(|byte| u8::from_str_radix(byte?, 16).ok())
+                    .collect::<Option<_>>()
+                    .ok_or("invalid state")?,
+            ),
+            _ => return Err(format!("unknown command '{}'", line.trim())),
+        };
+
+        Ok(Some(command))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KEYS: usize = 0x10;
+
+    fn parse(line: &str) -> Result<Option<Command>, String> {
+        Remote::parse(line, KEYS)
+    }
+
+    #[test]
+    fn numbers_are_hexadecimal() {
+        assert!(matches!(parse("peek 2a0"), Ok(Some(Command::Peek(0x02a0, 1)))));
+        assert!(matches!(parse("peek 2A0 10"), Ok(Some(Command::Peek(0x02a0, 0x10)))));
+        assert!(matches!(parse("  press   f "), Ok(Some(Command::Press(0xf)))));
+        assert!(matches!(parse("release a"), Ok(Some(Command::Release(0xa)))));
+
+        assert_eq!(parse("peek 0x200").unwrap_err(), "invalid number '0x200'");
+        assert_eq!(parse("peek +200").unwrap_err(), "invalid number '+200'");
+        assert_eq!(parse("press +5").unwrap_err(), "invalid number '+5'");
+        assert_eq!(parse("peek 10000").unwrap_err(), "invalid number '10000'");
+        assert_eq!(parse("peek 200 -1").unwrap_err(), "invalid number '-1'");
+    }
+
+    #[test]
+    fn bad_keys() {
+        assert_eq!(parse("press 10").unwrap_err(), "invalid key '10'");
+        assert_eq!(parse("release ffff").unwrap_err(), "invalid key 'ffff'");
+        assert_eq!(parse("press g").unwrap_err(), "invalid number 'g'");
+        assert!(matches!(Remote::parse("press 5", 4), Err(err) if err == "invalid key '5'"));
+    }
+
+    #[test]
+    fn load_state() {
+        assert!(matches!(parse("load 00ff7a"), Ok(Some(Command::Load(state))) if state == [0x00, 0xff, 0x7a]));
+
+        // Odd lengths and non hex digits are rejected
+        assert_eq!(parse("load 00f").unwrap_err(), "invalid state");
+        assert_eq!(parse("load 0g").unwrap_err(), "invalid state");
+        assert_eq!(parse("load +f").unwrap_err(), "invalid state");
+        assert_eq!(parse("load 00 ff").unwrap_err(), "unknown command 'load 00 ff'");
+        assert_eq!(parse("load").unwrap_err(), "unknown command 'load'");
+    }
+
+    #[test]
+    fn other_commands() {
+        assert!(matches!(parse(""), Ok(None)));
+        assert!(matches!(parse("   "), Ok(None)));
+        assert!(matches!(parse("pause"), Ok(Some(Command::Pause))));
+        assert!(matches!(parse("resume"), Ok(Some(Command::Resume))));
+        assert!(matches!(parse("screenshot"), Ok(Some(Command::Screenshot))));
+        assert!(matches!(parse("save"), Ok(Some(Command::Save))));
+
+        assert_eq!(parse("pause now").unwrap_err(), "unknown command 'pause now'");
+        assert_eq!(parse(" jump 200 ").unwrap_err(), "unknown command 'jump 200'");
+    }
+}
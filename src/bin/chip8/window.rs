@@ -15,6 +15,7 @@ mod video;
 const WINDOW_TITLE: &str = "CHIP-8 Emulator";
 const WINDOW_SCALE: u8 = 8;
 const WINDOW_FRAMERATE: u32 = 30;
+const WINDOW_FRAMERATE_LOCKED: u32 = 60;
 const WINDOW_BACKGROUND: Color = Color::RGB(0x28, 0x28, 0x28);
 const WINDOW_FOREGROUND: Color = Color::RGB(0xd5, 0xc4, 0xa1);
 
@@ -23,6 +24,26 @@ pub struct Window {
     audio: audio::AudioEngine,
     keyboard: keyboard::KeyboardEngine,
     events: EventPump,
+    hotkeys: Vec<Hotkey>,
+    title: String,
+}
+
+// Emulator state reported after each clock
+pub struct Status {
+    pub behind: bool,
+    pub dropped_cycles: u64,
+    pub paused: bool,
+    pub speed: f64,
+    pub waiting: bool,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Hotkey {
+    Pause,
+    StepFrame,
+    FastForward(bool),
+    SpeedUp,
+    SpeedDown,
 }
 
 impl Window {
@@ -30,7 +51,11 @@ impl Window {
         let sdl = sdl2::init()?;
 
         // Get options
-        let fps = options.fps.unwrap_or(WINDOW_FRAMERATE);
+        // Frame-locked emulation and netplay present every emulated frame
+        let fps = options.fps.unwrap_or(match (options.ipf, options.netplay) {
+            (None, None) => WINDOW_FRAMERATE,
+            _ => WINDOW_FRAMERATE_LOCKED,
+        });
         let scale = options.scale.unwrap_or(WINDOW_SCALE);
         let bg = options.bg.unwrap_or(WINDOW_BACKGROUND);
         let fg = options.fg.unwrap_or(WINDOW_FOREGROUND);
@@ -46,6 +71,8 @@ impl Window {
             audio,
             keyboard,
             events,
+            hotkeys: Vec::new(),
+            title: String::from(WINDOW_TITLE),
         })
     }
 
@@ -59,15 +86,46 @@ impl Window {
 
     pub fn run<F>(&mut self, mut f: F) -> Result<(), Box<dyn std::error::Error>>
     where
-        F: FnMut(&mut chip8::IO) -> Result<(), Box<dyn std::error::Error>>,
+        F: FnMut(&mut chip8::IO, &[chip8::KeyEvent], &[Hotkey]) -> Result<Status, Box<dyn std::error::Error>>,
     {
         self.display()?;
 
         while self.process_events() {
-            f(&mut self.get_io())?;
+            let hotkeys = std::mem::take(&mut self.hotkeys);
+            let events = self.keyboard.take_events();
+            let status = f(&mut self.get_io(), &events, &hotkeys)?;
+
+            self.update_title(&status)?;
 
             self.audio.render()?;
-            self.video.render(Instant::now())?;
+
+            // Frames are not presented while catching up
+            if !status.behind {
+                self.video.render(Instant::now())?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn update_title(&mut self, status: &Status) -> Result<(), error::Error> {
+        let mut title = String::from(WINDOW_TITLE);
+
+        if status.waiting {
+            title.push_str(" - waiting for peer");
+        } else if status.paused {
+            title.push_str(" - paused");
+        } else if status.speed != 1.0 {
+            title.push_str(&format!(" - x{}", status.speed));
+        }
+
+        if status.dropped_cycles > 0 {
+            title.push_str(&format!(" ({} cycles dropped)", status.dropped_cycles));
+        }
+
+        if title != self.title {
+            self.video.set_title(&title)?;
+            self.title = title;
         }
 
         Ok(())
@@ -100,6 +158,24 @@ impl Window {
                 } => {
                     return false;
                 }
+                // Window content lost, redraw it
+                Event::Window {
+                    win_event: WindowEvent::Exposed,
+                    ..
+                } => {
+                    self.video.invalidate();
+                }
+                // Emulation control
+                Event::KeyDown {
+                    keycode: Some(key),
+                    repeat: false,
+                    ..
+                } if Self::get_hotkey(key, true).is_some() => {
+                    self.hotkeys.extend(Self::get_hotkey(key, true));
+                }
+                Event::KeyUp { keycode: Some(key), .. } if Self::get_hotkey(key, false).is_some() => {
+                    self.hotkeys.extend(Self::get_hotkey(key, false));
+                }
                 // Key down
                 Event::KeyDown {
                     scancode: Some(key), ..
@@ -118,4 +194,15 @@ impl Window {
 
         true
     }
+
+    fn get_hotkey(key: Keycode, down: bool) -> Option<Hotkey> {
+        match (key, down) {
+            (Keycode::Space, true) => Some(Hotkey::Pause),
+            (Keycode::Period, true) => Some(Hotkey::StepFrame),
+            (Keycode::Tab, down) => Some(Hotkey::FastForward(down)),
+            (Keycode::Equals | Keycode::KpPlus, true) => Some(Hotkey::SpeedUp),
+            (Keycode::Minus | Keycode::KpMinus, true) => Some(Hotkey::SpeedDown),
+            _ => None,
+        }
+    }
 }
@@ -7,6 +7,7 @@ use crate::error;
 pub struct KeyboardEngine {
     map: HashMap<Scancode, usize>,
     buffer: Vec<bool>,
+    events: Vec<chip8::KeyEvent>,
 }
 
 impl KeyboardEngine {
@@ -18,17 +19,25 @@ impl KeyboardEngine {
                 .map(|(i, c)| Self::get_scancode(*c).map(|key| (key, i)))
                 .collect::<Result<_, _>>()?,
             buffer: vec![false; keys.len()],
+            events: Vec::new(),
         })
     }
 
     pub fn key_down(&mut self, key: &Scancode) {
+        // Auto-repeated key downs are not reported again
         if let Some(idx) = self.map.get(key).copied() {
+            if !self.buffer[idx] {
+                self.events.push(chip8::KeyEvent::Press(idx));
+            }
             self.buffer[idx] = true;
         }
     }
 
     pub fn key_up(&mut self, key: &Scancode) {
         if let Some(idx) = self.map.get(key).copied() {
+            if self.buffer[idx] {
+                self.events.push(chip8::KeyEvent::Release(idx));
+            }
             self.buffer[idx] = false;
         }
     }
@@ -37,6 +46,10 @@ impl KeyboardEngine {
         &self.buffer
     }
 
+    pub fn take_events(&mut self) -> Vec<chip8::KeyEvent> {
+        std::mem::take(&mut self.events)
+    }
+
     fn get_scancode(c: char) -> Result<Scancode, error::Error> {
         match c.to_ascii_lowercase() {
             '1' => Ok(Scancode::Num1),
@@ -4,13 +4,13 @@ use sdl2::pixels::Color;
 use sdl2::render;
 use sdl2::Sdl;
 
+use chip8::Screen;
+
 use crate::error;
 
 pub struct VideoEngine {
     canvas: render::WindowCanvas,
-    buffer: Vec<bool>,
-    width: usize,
-    height: usize,
+    buffer: chip8::Framebuffer,
     fps: time::Duration,
     last: Option<time::Instant>,
     bg: Color,
@@ -41,9 +41,8 @@ impl VideoEngine {
 
         canvas.set_scale(scale.into(), scale.into())?;
 
-        let buffer_sz = width
-            .checked_mul(height)
-            .ok_or(error::Error::ScreenTooLarge((width, height)))?;
+        let buffer =
+            chip8::Framebuffer::new(width, height).map_err(|_| error::Error::ScreenTooLarge((width, height)))?;
 
         let fps = time::Duration::from_secs(1)
             .checked_div(fps)
@@ -51,9 +50,7 @@ impl VideoEngine {
 
         Ok(Self {
             canvas,
-            buffer: vec![false; buffer_sz],
-            width,
-            height,
+            buffer,
             fps,
             last: None,
             bg,
@@ -62,28 +59,42 @@ impl VideoEngine {
     }
 
     pub fn render(&mut self, now: time::Instant) -> Result<(), error::Error> {
+        // Unchanged frames are not presented again
         let render = match self.last {
-            Some(prev) => now.duration_since(prev) >= self.fps,
+            Some(prev) => now.duration_since(prev) >= self.fps && self.buffer.is_changed(),
             None => true,
         };
 
         if render {
             self.update()?;
+            self.buffer.take_damage();
             self.last = Some(now);
         }
 
         Ok(())
     }
 
+    pub fn set_title(&mut self, title: &str) -> Result<(), error::Error> {
+        self.canvas
+            .window_mut()
+            .set_title(title)
+            .map_err(|err| err.to_string())?;
+        Ok(())
+    }
+
+    pub fn invalidate(&mut self) {
+        self.buffer.invalidate();
+    }
+
     fn update(&mut self) -> Result<(), error::Error> {
         self.canvas.set_draw_color(self.bg);
         self.canvas.clear();
 
         self.canvas.set_draw_color(self.fg);
 
-        for y in 0..self.height {
-            for x in 0..self.width {
-                if self.buffer[(y * self.width) + x] {
+        for y in 0..self.buffer.get_height() {
+            for x in 0..self.buffer.get_width() {
+                if self.buffer.get_pixel(x, y) {
                     self.canvas.draw_point((x as i32, y as i32))?;
                 }
             }
@@ -102,19 +113,27 @@ impl VideoEngine {
 }
 
 impl chip8::Screen for VideoEngine {
-    fn as_slice(&self) -> &[bool] {
-        &self.buffer
+    fn get_width(&self) -> usize {
+        self.buffer.get_width()
     }
 
-    fn as_mut_slice(&mut self) -> &mut [bool] {
-        &mut self.buffer
+    fn get_height(&self) -> usize {
+        self.buffer.get_height()
     }
 
-    fn get_width(&self) -> usize {
-        self.width
+    fn get_pixel(&self, x: usize, y: usize) -> bool {
+        self.buffer.get_pixel(x, y)
     }
 
-    fn get_height(&self) -> usize {
-        self.height
+    fn set_pixel(&mut self, x: usize, y: usize, on: bool) {
+        self.buffer.set_pixel(x, y, on)
+    }
+
+    fn clear(&mut self) {
+        self.buffer.clear()
+    }
+
+    fn draw(&mut self, x: u8, y: u8, byte: u8) -> bool {
+        self.buffer.draw(x, y, byte)
     }
 }
@@ -1,11 +1,242 @@
+#[cfg(feature = "std")]
+use crate::coverage::Coverage;
+use crate::crc16::Crc16;
+use crate::error::Error;
+#[cfg(feature = "std")]
+use crate::profiler::Profiler;
+use crate::random::{Lfsr, RandomSource};
+#[cfg(feature = "std")]
+use crate::smc::{SmcDetector, SmcEvent};
+use crate::state::{Reader, Writer};
+
+#[cfg(feature = "std")]
+mod mapped;
 mod ram;
-mod rng;
 mod timer;
 
-#[derive(Debug, Default)]
-pub struct Bus {
-    pub ram: ram::Ram,
-    pub rng: rng::Rng,
+#[cfg(feature = "std")]
+pub use mapped::MappedBus;
+pub use ram::Ram;
+pub use ram::MEMORY_SIZE;
+
+pub trait Bus {
+    fn read(&mut self, addr: u16) -> Result<u8, Error>;
+    fn write(&mut self, addr: u16, byte: u8) -> Result<(), Error>;
+
+    // Read without side effects, for tooling and state hashing
+    fn peek(&self, addr: u16) -> Result<u8, Error>;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Access {
+    Exec,
+    Sprite,
+    Read,
+    Write,
+}
+
+// Custom generators need allocation, without std only the default one is available
+#[cfg(feature = "std")]
+pub type Generator = Box<dyn RandomSource + Send>;
+#[cfg(not(feature = "std"))]
+pub type Generator = Lfsr;
+
+// Board part of a save state, read before anything is applied
+#[derive(Debug)]
+pub struct BoardState<'a> {
+    mem: &'a [u8],
+    rng: u64,
+    dt: u8,
+    st: u8,
+}
+
+#[derive(Debug)]
+pub struct Board<B> {
+    pub mem: B,
+    pub rng: Generator,
     pub dt: timer::Timer,
     pub st: timer::Timer,
+    #[cfg(feature = "std")]
+    pub coverage: Option<Coverage>,
+    #[cfg(feature = "std")]
+    pub profiler: Option<Profiler>,
+    #[cfg(feature = "std")]
+    pub smc: Option<SmcDetector>,
+}
+
+impl<B: Bus> Board<B> {
+    pub fn new(mem: B) -> Self {
+        Self {
+            mem,
+            #[cfg(feature = "std")]
+            rng: Box::new(Lfsr::new()),
+            #[cfg(not(feature = "std"))]
+            rng: Lfsr::new(),
+            dt: Default::default(),
+            st: Default::default(),
+            #[cfg(feature = "std")]
+            coverage: None,
+            #[cfg(feature = "std")]
+            profiler: None,
+            #[cfg(feature = "std")]
+            smc: None,
+        }
+    }
+
+    // Tooling hooks below compile to nothing without std
+    #[inline]
+    pub fn mark(&mut self, _addr: u16, _access: Access) {
+        #[cfg(feature = "std")]
+        if let Some(coverage) = &mut self.coverage {
+            coverage.mark(_addr, _access);
+        }
+    }
+
+    #[inline]
+    pub fn exec(&mut self, pc: u16) {
+        self.mark(pc, Access::Exec);
+        self.mark(pc.wrapping_add(1), Access::Exec);
+
+        #[cfg(feature = "std")]
+        if let Some(profiler) = &mut self.profiler {
+            profiler.exec(pc);
+        }
+
+        #[cfg(feature = "std")]
+        if let Some(smc) = &mut self.smc {
+            smc.exec(pc);
+        }
+    }
+
+    #[inline]
+    pub fn call(&mut self, _addr: u16) {
+        #[cfg(feature = "std")]
+        if let Some(profiler) = &mut self.profiler {
+            profiler.call(_addr);
+        }
+    }
+
+    #[inline]
+    pub fn ret(&mut self) {
+        #[cfg(feature = "std")]
+        if let Some(profiler) = &mut self.profiler {
+            profiler.ret();
+        }
+    }
+
+    // Unmapped addresses count as zero, as in save states
+    pub fn checksum(&self, crc: &mut Crc16) {
+        for addr in 0..(MEMORY_SIZE as u16) {
+            crc.update(self.mem.peek(addr).unwrap_or_default());
+        }
+
+        crc.update_u64(self.rng.get_state());
+        crc.update(self.dt.get());
+        crc.update(self.st.get());
+    }
+
+    // Unmapped addresses are saved as zero, and only bytes that differ are written back
+    pub fn save(&self, w: &mut Writer) {
+        for addr in 0..(MEMORY_SIZE as u16) {
+            w.u8(self.mem.peek(addr).unwrap_or_default());
+        }
+
+        w.u64(self.rng.get_state());
+        w.u8(self.dt.get());
+        w.u8(self.st.get());
+    }
+
+    pub fn stage<'a>(&self, r: &mut Reader<'a>) -> Result<BoardState<'a>, Error> {
+        Ok(BoardState {
+            mem: r.take(MEMORY_SIZE)?,
+            rng: r.u64()?,
+            dt: r.u8()?,
+            st: r.u8()?,
+        })
+    }
+
+    // Memory writes may fail on mapped devices, bytes already written are then put back
+    pub fn restore(&mut self, state: &BoardState) -> Result<(), Error> {
+        let mut old = [0x00; MEMORY_SIZE];
+        for (addr, byte) in (0..(MEMORY_SIZE as u16)).zip(old.iter_mut()) {
+            *byte = self.mem.peek(addr).unwrap_or_default();
+        }
+
+        for addr in 0..(MEMORY_SIZE as u16) {
+            let byte = state.mem[addr as usize];
+
+            if self.mem.peek(addr).is_err() || old[addr as usize] == byte {
+                continue;
+            }
+
+            if let Err(err) = self.mem.write(addr, byte) {
+                for addr in (0..addr).filter(|addr| old[*addr as usize] != state.mem[*addr as usize]) {
+                    let _ = self.mem.write(addr, old[addr as usize]);
+                }
+
+                return Err(err);
+            }
+        }
+
+        self.rng.set_state(state.rng);
+        self.dt.set(state.dt);
+        self.st.set(state.st);
+
+        Ok(())
+    }
+
+    // Data writes from the CPU, checked for modifications of already executed instructions
+    #[inline]
+    pub fn write(&mut self, pc: u16, addr: u16, byte: u8) -> Result<(), Error> {
+        self.store(Some(pc), addr, byte)?;
+        self.mark(addr, Access::Write);
+
+        Ok(())
+    }
+
+    // Writes from debuggers, reported without a program counter and left out of coverage
+    pub fn poke(&mut self, addr: u16, byte: u8) -> Result<(), Error> {
+        self.store(None, addr, byte)
+    }
+
+    #[cfg(not(feature = "std"))]
+    #[inline]
+    fn store(&mut self, _pc: Option<u16>, addr: u16, byte: u8) -> Result<(), Error> {
+        self.mem.write(addr, byte)
+    }
+
+    #[cfg(feature = "std")]
+    #[inline]
+    fn store(&mut self, pc: Option<u16>, addr: u16, byte: u8) -> Result<(), Error> {
+        match &mut self.smc {
+            Some(smc) if smc.get_instructions(addr).next().is_some() => {
+                let opcode = |mem: &B, start: u16| -> u16 {
+                    let hi = mem.peek(start).unwrap_or_default();
+                    let lo = mem.peek(start.wrapping_add(1)).unwrap_or_default();
+                    ((hi as u16) << 8) | (lo as u16)
+                };
+
+                let starts: Vec<_> = smc.get_instructions(addr).collect();
+                let old: Vec<_> = starts.iter().map(|start| opcode(&self.mem, *start)).collect();
+
+                self.mem.write(addr, byte)?;
+
+                for (start, old) in starts.into_iter().zip(old) {
+                    let new = opcode(&self.mem, start);
+
+                    if new != old {
+                        smc.push(SmcEvent {
+                            pc,
+                            addr: start,
+                            old,
+                            new,
+                        });
+                    }
+                }
+
+                Ok(())
+            }
+            _ => self.mem.write(addr, byte),
+        }
+    }
 }
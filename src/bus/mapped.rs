@@ -0,0 +1,76 @@
+use std::fmt;
+use std::ops::Range;
+
+use crate::bus::Bus;
+use crate::error::Error;
+
+pub struct MappedBus<B> {
+    base: B,
+    devices: Vec<(Range<u16>, Box<dyn Bus + Send>)>,
+}
+
+impl<B: Bus> MappedBus<B> {
+    pub fn new(base: B) -> Self {
+        Self {
+            base,
+            devices: Vec::new(),
+        }
+    }
+
+    // Devices see addresses relative to the start of their range, and cannot share addresses
+    pub fn map<D: Bus + Send + 'static>(&mut self, range: Range<u16>, device: D) -> Result<(), Error> {
+        if range.is_empty() || self.devices.iter().any(|(mapped, _)| overlaps(mapped, &range)) {
+            return Err(Error::InvalidMapping(range.start, range.end));
+        }
+
+        self.devices.push((range, Box::new(device)));
+        Ok(())
+    }
+
+    pub fn get_base(&self) -> &B {
+        &self.base
+    }
+
+    pub fn get_base_mut(&mut self) -> &mut B {
+        &mut self.base
+    }
+}
+
+impl<B: Bus> Bus for MappedBus<B> {
+    fn read(&mut self, addr: u16) -> Result<u8, Error> {
+        match self.devices.iter_mut().find(|(range, _)| range.contains(&addr)) {
+            Some((range, device)) => device.read(addr - range.start),
+            None => self.base.read(addr),
+        }
+    }
+
+    fn write(&mut self, addr: u16, byte: u8) -> Result<(), Error> {
+        match self.devices.iter_mut().find(|(range, _)| range.contains(&addr)) {
+            Some((range, device)) => device.write(addr - range.start, byte),
+            None => self.base.write(addr, byte),
+        }
+    }
+
+    fn peek(&self, addr: u16) -> Result<u8, Error> {
+        match self.devices.iter().find(|(range, _)| range.contains(&addr)) {
+            Some((range, device)) => device.peek(addr - range.start),
+            None => self.base.peek(addr),
+        }
+    }
+}
+
+fn overlaps(a: &Range<u16>, b: &Range<u16>) -> bool {
+    a.start < b.end && b.start < a.end
+}
+
+impl<B: fmt::Debug> fmt::Debug for MappedBus<B> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        fmt.debug_struct("MappedBus")
+            .field("base", &self.base)
+            .field(
+                "devices",
+                &self.devices.iter().map(|(range, _)| range).collect::<Vec<_>>(),
+            )
+            .finish()
+    }
+}
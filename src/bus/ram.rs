@@ -1,25 +1,33 @@
+use crate::bus::Bus;
 use crate::error::Error;
 
-const MEMORY_SIZE: usize = 0x1000;
+pub const MEMORY_SIZE: usize = 0x1000;
 
 #[derive(Debug)]
 pub struct Ram {
     memory: [u8; MEMORY_SIZE],
 }
 
-impl Ram {
-    pub fn read(&mut self, addr: u16) -> Result<u8, Error> {
+impl Bus for Ram {
+    #[inline]
+    fn read(&mut self, addr: u16) -> Result<u8, Error> {
+        self.peek(addr)
+    }
+
+    #[inline]
+    fn write(&mut self, addr: u16, byte: u8) -> Result<(), Error> {
         if (addr as usize) < self.memory.len() {
-            Ok(self.memory[addr as usize])
+            self.memory[addr as usize] = byte;
+            Ok(())
         } else {
             Err(Error::RamOutOfRange(addr))
         }
     }
 
-    pub fn write(&mut self, addr: u16, byte: u8) -> Result<(), Error> {
+    #[inline]
+    fn peek(&self, addr: u16) -> Result<u8, Error> {
         if (addr as usize) < self.memory.len() {
-            self.memory[addr as usize] = byte;
-            Ok(())
+            Ok(self.memory[addr as usize])
         } else {
             Err(Error::RamOutOfRange(addr))
         }
@@ -1,33 +1,258 @@
-use std::time;
+use core::time;
 
-use crate::error::Error;
+mod source;
+
+#[cfg(feature = "std")]
+pub use source::ManualTime;
+#[cfg(feature = "std")]
+pub use source::RealTime;
+pub use source::ScaledTime;
+pub use source::TimeSource;
 
 #[derive(Debug)]
 pub struct Clock {
-    freq: time::Duration,
-    last: Option<time::Instant>,
+    millihertz: u128,
+    start: Option<time::Duration>,
+    base: u64,
+    ticks: u64,
 }
 
 impl Clock {
-    pub fn new(freq: time::Duration) -> Self {
-        Self { freq, last: None }
-    }
-
-    pub fn tick<F>(&mut self, now: time::Instant, mut f: F) -> Result<(), Error>
-    where
-        F: FnMut() -> Result<(), Error>,
-    {
-        self.last = if let Some(mut last) = self.last {
-            while now.saturating_duration_since(last) >= self.freq {
-                f()?;
-                last += self.freq;
+    pub fn new(freq: f32) -> Self {
+        Self {
+            millihertz: ((freq as f64) * 1000.0 + 0.5) as u128,
+            start: None,
+            base: 0,
+            ticks: 0,
+        }
+    }
+
+    // Ticks are counted from the first call rather than accumulated, so no rounding error builds up
+    pub fn tick(&mut self, now: time::Duration) -> u64 {
+        let start = match self.start {
+            Some(start) => start,
+            None => {
+                self.start = Some(now);
+                self.base = 1;
+                now
             }
-            Some(last)
-        } else {
-            f()?;
-            Some(now)
         };
 
-        Ok(())
+        let elapsed = now.saturating_sub(start).as_nanos() * self.millihertz / 1_000_000_000_000;
+        let target = self.base + elapsed as u64;
+        let ticks = target.saturating_sub(self.ticks);
+
+        self.ticks = self.ticks.max(target);
+
+        ticks
+    }
+
+    // Continue counting at another frequency from now, ticks not yet collected are lost
+    pub fn set_freq(&mut self, freq: f32, now: time::Duration) {
+        self.millihertz = Self::new(freq).millihertz;
+
+        if self.start.is_some() {
+            self.start = Some(now);
+            self.base = self.ticks;
+        }
+    }
+
+    // Gives back ticks that were collected but never run, counting resumes from now as if they never elapsed
+    pub fn rewind(&mut self, ticks: u64, now: time::Duration) {
+        self.ticks -= ticks;
+
+        if self.start.is_some() {
+            self.start = Some(now);
+            self.base = self.ticks;
+        }
+    }
+
+    // Time at which the next tick is due, ticks are not missed when running until then
+    pub fn next_tick(&self) -> Option<time::Duration> {
+        let start = self.start?;
+        let ticks = (self.ticks + 1 - self.base) as u128;
+
+        if self.millihertz == 0 {
+            return None;
+        }
+
+        let nanos = (ticks * 1_000_000_000_000).div_ceil(self.millihertz);
+        Some(start + time::Duration::from_nanos(nanos as u64))
+    }
+}
+
+// Emulated time, stopped while paused and scaled by the speed multiplier
+#[derive(Debug)]
+pub struct Timebase {
+    host: time::Duration,
+    emulated: time::Duration,
+    speed: f64,
+    paused: bool,
+}
+
+impl Timebase {
+    pub fn new(host: time::Duration) -> Self {
+        Self {
+            host,
+            emulated: time::Duration::ZERO,
+            speed: 1.0,
+            paused: false,
+        }
+    }
+
+    pub fn now(&self, host: time::Duration) -> time::Duration {
+        if self.paused {
+            self.emulated
+        } else {
+            self.emulated + host.saturating_sub(self.host).mul_f64(self.speed)
+        }
+    }
+
+    // Keeps the emulated time when host time jumps, e.g. when switching time sources
+    pub fn rebase(&mut self, host: time::Duration, new_host: time::Duration) {
+        self.emulated = self.now(host);
+        self.host = new_host;
+    }
+
+    pub fn get_speed(&self) -> f64 {
+        self.speed
+    }
+
+    pub fn set_speed(&mut self, speed: f64, host: time::Duration) {
+        self.rebase(host, host);
+        self.speed = speed;
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+
+    pub fn pause(&mut self, host: time::Duration) {
+        self.rebase(host, host);
+        self.paused = true;
+    }
+
+    pub fn resume(&mut self, host: time::Duration) {
+        self.host = host;
+        self.paused = false;
+    }
+
+    pub fn advance_to(&mut self, emulated: time::Duration) {
+        self.emulated = self.emulated.max(emulated);
+    }
+}
+
+// What gives when the host falls behind by more than the catch-up budget
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CatchUp {
+    // Time past the budget is skipped, CPU cycles and timer ticks alike, and counted as dropped
+    Drop,
+    // Time past the budget is never emulated, the whole machine runs slower and nothing is dropped
+    SlowDown,
+    // Missed time is kept and run over the next calls, the host should not present frames meanwhile
+    SkipFrames,
+}
+
+// Longest backlog kept by SkipFrames, in budgets, older time is dropped so that a long stall is not fast-forwarded
+const MAX_BACKLOG: u32 = 10;
+
+#[derive(Debug)]
+pub struct Limiter {
+    policy: CatchUp,
+    budget: time::Duration,
+    backlog: (u64, u64),
+    dropped: u64,
+}
+
+impl Limiter {
+    pub fn new(policy: CatchUp, budget: time::Duration) -> Self {
+        Self {
+            policy,
+            budget,
+            backlog: (0, 0),
+            dropped: 0,
+        }
+    }
+
+    pub fn get_policy(&self) -> CatchUp {
+        self.policy
+    }
+
+    pub fn get_dropped(&self) -> u64 {
+        self.dropped
+    }
+
+    pub fn is_behind(&self) -> bool {
+        self.backlog != (0, 0)
+    }
+
+    // Returns the cycles and timer ticks to run out of the elapsed ones
+    pub fn limit(&mut self, cycles: u64, ticks: u64, freq: f32, tick_freq: f32) -> (u64, u64) {
+        let max_cycles = self.get_budget(freq);
+        let max_ticks = self.get_budget(tick_freq);
+
+        match self.policy {
+            CatchUp::Drop | CatchUp::SlowDown => {
+                let run = (cycles.min(max_cycles), ticks.min(max_ticks));
+
+                if self.policy == CatchUp::Drop {
+                    self.dropped += cycles - run.0;
+                }
+
+                run
+            }
+            CatchUp::SkipFrames => {
+                let kept = (max_cycles * (MAX_BACKLOG as u64), max_ticks * (MAX_BACKLOG as u64));
+                let backlog = (self.backlog.0 + cycles, self.backlog.1 + ticks);
+                self.dropped += backlog.0.saturating_sub(kept.0);
+
+                self.backlog = (backlog.0.min(kept.0), backlog.1.min(kept.1));
+
+                let run = (self.backlog.0.min(max_cycles), self.backlog.1.min(max_ticks));
+                self.backlog.0 -= run.0;
+                self.backlog.1 -= run.1;
+                run
+            }
+        }
+    }
+
+    // Frames run the CPU in lockstep with timers, so cycles can only be dropped along with whole frames
+    pub fn limit_frames(&mut self, frames: u64, freq: f32, tick_freq: f32) -> u64 {
+        let max_frames = self.get_budget(tick_freq);
+        let to_cycles = |frames: u64| (((frames as f64) * (freq as f64)) / (tick_freq as f64)) as u64;
+
+        match self.policy {
+            CatchUp::Drop | CatchUp::SlowDown => {
+                let run = frames.min(max_frames);
+
+                if self.policy == CatchUp::Drop {
+                    self.dropped += to_cycles(frames - run);
+                }
+
+                run
+            }
+            CatchUp::SkipFrames => {
+                let kept = max_frames * (MAX_BACKLOG as u64);
+                let backlog = self.backlog.1 + frames;
+                self.dropped += to_cycles(backlog.saturating_sub(kept));
+
+                self.backlog.1 = backlog.min(kept);
+
+                let run = self.backlog.1.min(max_frames);
+                self.backlog.1 -= run;
+                run
+            }
+        }
+    }
+
+    // Rounded up, floating point rounding functions are not available without std
+    fn get_budget(&self, freq: f32) -> u64 {
+        let budget = self.budget.as_secs_f64() * (freq as f64);
+        let truncated = budget as u64;
+
+        match (truncated as f64) < budget {
+            true => truncated + 1,
+            false => truncated.max(1),
+        }
     }
 }
@@ -0,0 +1,94 @@
+use core::fmt;
+#[cfg(feature = "std")]
+use core::sync::atomic::{AtomicU64, Ordering};
+use core::time;
+#[cfg(feature = "std")]
+use std::sync::Arc;
+
+use crate::error;
+
+// Monotonic time elapsed since an arbitrary origin
+pub trait TimeSource: fmt::Debug {
+    fn now(&self) -> time::Duration;
+}
+
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Copy)]
+pub struct RealTime {
+    start: std::time::Instant,
+}
+
+// Clones share the same time, so a test can keep a handle while the emulator owns the source
+#[cfg(feature = "std")]
+#[derive(Debug, Clone, Default)]
+pub struct ManualTime {
+    nanos: Arc<AtomicU64>,
+}
+
+// Runs another source faster or slower, on top of the runtime speed of the machine
+#[derive(Debug, Clone)]
+pub struct ScaledTime<T> {
+    source: T,
+    scale: f64,
+}
+
+#[cfg(feature = "std")]
+impl RealTime {
+    pub fn new() -> Self {
+        Self {
+            start: std::time::Instant::now(),
+        }
+    }
+}
+
+#[cfg(feature = "std")]
+impl Default for RealTime {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(feature = "std")]
+impl TimeSource for RealTime {
+    fn now(&self) -> time::Duration {
+        self.start.elapsed()
+    }
+}
+
+#[cfg(feature = "std")]
+impl ManualTime {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn advance(&self, duration: time::Duration) {
+        self.nanos.fetch_add(duration.as_nanos() as u64, Ordering::SeqCst);
+    }
+}
+
+#[cfg(feature = "std")]
+impl TimeSource for ManualTime {
+    fn now(&self) -> time::Duration {
+        time::Duration::from_nanos(self.nanos.load(Ordering::SeqCst))
+    }
+}
+
+impl<T: TimeSource> ScaledTime<T> {
+    pub fn new(source: T, scale: f64) -> Result<Self, error::Error> {
+        if !scale.is_normal() || scale.is_sign_negative() {
+            return Err(error::Error::InvalidTimeScale(scale));
+        }
+
+        Ok(Self { source, scale })
+    }
+
+    pub fn get_scale(&self) -> f64 {
+        self.scale
+    }
+}
+
+impl<T: TimeSource> TimeSource for ScaledTime<T> {
+    fn now(&self) -> time::Duration {
+        self.source.now().mul_f64(self.scale)
+    }
+}
@@ -0,0 +1,193 @@
+use std::io;
+
+use crate::bus::{Access, MEMORY_SIZE};
+
+const FLAG_EXEC: u8 = 0x01;
+const FLAG_SPRITE: u8 = 0x02;
+const FLAG_READ: u8 = 0x04;
+const FLAG_WRITE: u8 = 0x08;
+const FLAG_DATA: u8 = FLAG_SPRITE | FLAG_READ | FLAG_WRITE;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Region {
+    Unused,
+    Code,
+    Data,
+    Mixed,
+}
+
+#[derive(Debug, Clone)]
+pub struct Coverage {
+    flags: Vec<u8>,
+}
+
+impl Coverage {
+    pub fn new() -> Self {
+        Self {
+            flags: vec![0x00; MEMORY_SIZE],
+        }
+    }
+
+    pub fn mark(&mut self, addr: u16, access: Access) {
+        if let Some(flags) = self.flags.get_mut(addr as usize) {
+            *flags |= access.flag();
+        }
+    }
+
+    pub fn has(&self, addr: u16, access: Access) -> bool {
+        self.get_flags(addr) & access.flag() != 0
+    }
+
+    pub fn get_region(&self, addr: u16) -> Region {
+        let flags = self.get_flags(addr);
+
+        match (flags & FLAG_EXEC != 0, flags & FLAG_DATA != 0) {
+            (false, false) => Region::Unused,
+            (true, false) => Region::Code,
+            (false, true) => Region::Data,
+            (true, true) => Region::Mixed,
+        }
+    }
+
+    // Contiguous ranges of addresses sharing the same region kind, end address excluded
+    pub fn get_regions(&self) -> Vec<(u16, u16, Region)> {
+        let mut regions: Vec<(u16, u16, Region)> = Vec::new();
+
+        for addr in 0..(self.flags.len() as u16) {
+            let region = self.get_region(addr);
+
+            match regions.last_mut() {
+                Some((_, end, last)) if *last == region => *end = addr + 1,
+                _ => regions.push((addr, addr + 1, region)),
+            }
+        }
+
+        regions
+    }
+
+    pub fn write_json<W: io::Write>(&self, mut w: W) -> io::Result<()> {
+        writeln!(w, "{{")?;
+        writeln!(w, "  \"regions\": [")?;
+
+        let regions = self.get_regions();
+
+        for (i, (start, end, region)) in regions.iter().enumerate() {
+            let sep = if i + 1 < regions.len() { "," } else { "" };
+            writeln!(
+                w,
+                "    {{ \"start\": {}, \"end\": {}, \"kind\": \"{}\" }}{}",
+                start,
+                end,
+                region.name(),
+                sep
+            )?;
+        }
+
+        writeln!(w, "  ],")?;
+        writeln!(w, "  \"addresses\": [")?;
+
+        let used: Vec<_> = (0..(self.flags.len() as u16))
+            .filter(|addr| self.get_flags(*addr) != 0x00)
+            .collect();
+
+        for (i, addr) in used.iter().copied().enumerate() {
+            let accesses: Vec<_> = Access::ALL
+                .iter()
+                .filter(|access| self.has(addr, **access))
+                .map(|access| format!("\"{}\"", access.name()))
+                .collect();
+
+            let sep = if i + 1 < used.len() { "," } else { "" };
+            writeln!(
+                w,
+                "    {{ \"addr\": {}, \"access\": [{}] }}{}",
+                addr,
+                accesses.join(", "),
+                sep
+            )?;
+        }
+
+        writeln!(w, "  ]")?;
+        writeln!(w, "}}")?;
+
+        w.flush()
+    }
+
+    pub fn write_hexdump<W: io::Write>(&self, memory: &[u8], mut w: W) -> io::Result<()> {
+        writeln!(
+            w,
+            "# C: code, S: sprite, R: read, W: written, D: several data accesses, !: code and data"
+        )?;
+
+        for (row, bytes) in memory.chunks(16).enumerate() {
+            let base = (row * 16) as u16;
+
+            // Skip lines never touched during execution
+            if (0..(bytes.len() as u16)).all(|i| self.get_flags(base + i) == 0x00) {
+                continue;
+            }
+
+            let hex: Vec<_> = bytes.iter().map(|byte| format!("{:02x}", byte)).collect();
+            let tags: String = (0..(bytes.len() as u16)).map(|i| self.get_tag(base + i)).collect();
+
+            writeln!(w, "{:04x}: {:<47}  |{}|", base, hex.join(" "), tags)?;
+        }
+
+        w.flush()
+    }
+
+    fn get_flags(&self, addr: u16) -> u8 {
+        self.flags.get(addr as usize).copied().unwrap_or_default()
+    }
+
+    fn get_tag(&self, addr: u16) -> char {
+        match (self.get_region(addr), self.get_flags(addr) & FLAG_DATA) {
+            (Region::Unused, _) => '.',
+            (Region::Code, _) => 'C',
+            (Region::Mixed, _) => '!',
+            (Region::Data, FLAG_SPRITE) => 'S',
+            (Region::Data, FLAG_READ) => 'R',
+            (Region::Data, FLAG_WRITE) => 'W',
+            (Region::Data, _) => 'D',
+        }
+    }
+}
+
+impl Default for Coverage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Access {
+    const ALL: [Access; 4] = [Access::Exec, Access::Sprite, Access::Read, Access::Write];
+
+    fn flag(self) -> u8 {
+        match self {
+            Self::Exec => FLAG_EXEC,
+            Self::Sprite => FLAG_SPRITE,
+            Self::Read => FLAG_READ,
+            Self::Write => FLAG_WRITE,
+        }
+    }
+
+    fn name(self) -> &'static str {
+        match self {
+            Self::Exec => "exec",
+            Self::Sprite => "sprite",
+            Self::Read => "read",
+            Self::Write => "write",
+        }
+    }
+}
+
+impl Region {
+    fn name(self) -> &'static str {
+        match self {
+            Self::Unused => "unused",
+            Self::Code => "code",
+            Self::Data => "data",
+            Self::Mixed => "mixed",
+        }
+    }
+}
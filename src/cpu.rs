@@ -1,8 +1,12 @@
-use crate::bus::Bus;
+use crate::bus::{Access, Board, Bus};
+use crate::crc16::Crc16;
 use crate::error::Error;
-use crate::io::IO;
+use crate::io::{KeyEvent, IO};
+#[cfg(not(feature = "std"))]
+use crate::random::RandomSource;
+use crate::state::{Reader, Writer};
 
-type Result<T> = std::result::Result<T, Error>;
+type Result<T> = core::result::Result<T, Error>;
 
 const FONT_SIZE: u16 = 5;
 const OPCODE_SIZE: u16 = 2;
@@ -45,6 +49,20 @@ pub struct Cpu {
     sp: u8,
     stack: [u16; 0x10],
     ft: u16,
+    wait_mode: WaitMode,
+    waiting: bool,
+    held: Option<u8>,
+    pressed: u16,
+    released: u16,
+}
+
+// Condition completing FX0A, the VIP also sounds the buzzer while the key is held
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum WaitMode {
+    #[default]
+    Press,
+    Release,
+    Vip,
 }
 
 #[derive(Debug)]
@@ -62,10 +80,144 @@ impl Cpu {
         self.ft = ft;
     }
 
-    pub fn cycle(&mut self, bus: &mut Bus, io: &mut IO) -> Result<()> {
+    pub fn get_v(&self) -> [u8; 0x10] {
+        self.v
+    }
+
+    pub fn get_i(&self) -> u16 {
+        self.i
+    }
+
+    pub fn get_pc(&self) -> u16 {
+        self.pc
+    }
+
+    pub fn get_stack(&self) -> &[u16] {
+        &self.stack[..(self.sp as usize)]
+    }
+
+    pub fn set_v(&mut self, x: usize, value: u8) -> Result<()> {
+        match self.v.get_mut(x) {
+            Some(v) => {
+                *v = value;
+                Ok(())
+            }
+            None => Err(Error::RegisterOutOfRange(x)),
+        }
+    }
+
+    pub fn set_i(&mut self, i: u16) {
+        self.i = i;
+    }
+
+    pub fn set_pc(&mut self, pc: u16) {
+        self.pc = pc;
+        self.waiting = false;
+    }
+
+    pub fn set_stack(&mut self, stack: &[u16]) -> Result<()> {
+        if stack.len() <= self.stack.len() {
+            self.stack[..stack.len()].copy_from_slice(stack);
+            self.sp = stack.len() as u8;
+            Ok(())
+        } else {
+            Err(Error::StackOverflow)
+        }
+    }
+
+    pub fn get_wait_mode(&self) -> WaitMode {
+        self.wait_mode
+    }
+
+    pub fn set_wait_mode(&mut self, mode: WaitMode) {
+        self.wait_mode = mode;
+    }
+
+    // Only edges happening while FX0A waits are latched, releases only count for the key pressed first
+    pub fn key_event(&mut self, event: KeyEvent) {
+        if self.waiting {
+            match event {
+                KeyEvent::Press(key) => {
+                    self.pressed |= 1 << key;
+                    self.held = self.held.or(Some(key as u8));
+                }
+                KeyEvent::Release(key) if self.held == Some(key as u8) => self.released |= 1 << key,
+                KeyEvent::Release(_) => {}
+            }
+        }
+    }
+
+    pub fn is_beeping(&self) -> bool {
+        match (self.wait_mode, self.held) {
+            (WaitMode::Vip, Some(key)) => self.waiting && (self.released & (1 << key)) == 0,
+            _ => false,
+        }
+    }
+
+    pub fn checksum(&self, crc: &mut Crc16) {
+        self.v.iter().for_each(|v| crc.update(*v));
+        crc.update_u16(self.i);
+        crc.update_u16(self.pc);
+        crc.update(self.sp);
+        self.stack.iter().for_each(|addr| crc.update_u16(*addr));
+        crc.update_u16(self.ft);
+        crc.update(self.wait_mode as u8);
+        crc.update(self.waiting as u8);
+        crc.update(self.held.unwrap_or(0xff));
+        crc.update_u16(self.pressed);
+        crc.update_u16(self.released);
+    }
+
+    // The wait mode is configuration, it is left out of save states
+    pub fn save(&self, w: &mut Writer) {
+        w.put(&self.v);
+        w.u16(self.i);
+        w.u16(self.pc);
+        w.u8(self.sp);
+        self.stack.iter().for_each(|addr| w.u16(*addr));
+        w.u16(self.ft);
+        w.u8(self.waiting as u8);
+        w.u8(self.held.unwrap_or(0xff));
+        w.u16(self.pressed);
+        w.u16(self.released);
+    }
+
+    // Parsed and validated apart from the running CPU, which is replaced once the whole state is known to be valid
+    pub fn stage(&self, r: &mut Reader) -> Result<Cpu> {
+        let mut cpu = Cpu {
+            wait_mode: self.wait_mode,
+            ..Default::default()
+        };
+
+        cpu.v.copy_from_slice(r.take(0x10)?);
+        cpu.i = r.u16()?;
+        cpu.pc = r.u16()?;
+        cpu.sp = r.u8()?;
+        for addr in cpu.stack.iter_mut() {
+            *addr = r.u16()?;
+        }
+        cpu.ft = r.u16()?;
+        cpu.waiting = r.u8()? != 0;
+        cpu.held = match r.u8()? {
+            0xff => None,
+            key => Some(key),
+        };
+        cpu.pressed = r.u16()?;
+        cpu.released = r.u16()?;
+
+        if (cpu.sp as usize) > cpu.stack.len() || cpu.held.is_some_and(|key| key >= 0x10) {
+            return Err(Error::InvalidState);
+        }
+
+        Ok(cpu)
+    }
+
+    pub fn cycle<B: Bus>(&mut self, bus: &mut Board<B>, io: &mut IO) -> Result<()> {
         // Fetch
-        let hi = bus.ram.read(self.pc)?;
-        let lo = bus.ram.read(self.pc.wrapping_add(1))?;
+        let hi = bus.mem.read(self.pc)?;
+        let lo = bus.mem.read(self.pc.wrapping_add(1))?;
+
+        bus.exec(self.pc);
 
         let opcode = [(hi >> 4) & 0xf, hi & 0xf, (lo >> 4) & 0xf, lo & 0xf];
 
@@ -119,129 +271,134 @@ impl Cpu {
         Ok(())
     }
 
-    fn op_nop(&mut self, _bus: &mut Bus, _io: &mut IO) -> Result<ProgramCounter> {
+    fn op_nop<B: Bus>(&mut self, _bus: &mut Board<B>, _io: &mut IO) -> Result<ProgramCounter> {
         Ok(ProgramCounter::Next)
     }
 
-    fn op_cls(&mut self, _bus: &mut Bus, io: &mut IO) -> Result<ProgramCounter> {
+    fn op_cls<B: Bus>(&mut self, _bus: &mut Board<B>, io: &mut IO) -> Result<ProgramCounter> {
         io.screen.clear();
         Ok(ProgramCounter::Next)
     }
 
-    fn op_ret(&mut self, _bus: &mut Bus, _io: &mut IO) -> Result<ProgramCounter> {
+    fn op_ret<B: Bus>(&mut self, bus: &mut Board<B>, _io: &mut IO) -> Result<ProgramCounter> {
         let addr = self.stack_pop()?;
+        bus.ret();
+
         Ok(ProgramCounter::Jump(addr))
     }
 
-    fn op_jmp(&mut self, _bus: &mut Bus, _io: &mut IO, nnn: u16) -> Result<ProgramCounter> {
+    fn op_jmp<B: Bus>(&mut self, _bus: &mut Board<B>, _io: &mut IO, nnn: u16) -> Result<ProgramCounter> {
         Ok(ProgramCounter::Jump(nnn))
     }
 
-    fn op_call(&mut self, _bus: &mut Bus, _io: &mut IO, nnn: u16) -> Result<ProgramCounter> {
+    fn op_call<B: Bus>(&mut self, bus: &mut Board<B>, _io: &mut IO, nnn: u16) -> Result<ProgramCounter> {
         self.stack_push(self.pc.wrapping_add(OPCODE_SIZE))?;
+        bus.call(nnn);
+
         Ok(ProgramCounter::Jump(nnn))
     }
 
-    fn op_sei(&mut self, _bus: &mut Bus, _io: &mut IO, x: usize, kk: u8) -> Result<ProgramCounter> {
+    fn op_sei<B: Bus>(&mut self, _bus: &mut Board<B>, _io: &mut IO, x: usize, kk: u8) -> Result<ProgramCounter> {
         Ok(ProgramCounter::skip_if(self.v[x] == kk))
     }
 
-    fn op_snei(&mut self, _bus: &mut Bus, _io: &mut IO, x: usize, kk: u8) -> Result<ProgramCounter> {
+    fn op_snei<B: Bus>(&mut self, _bus: &mut Board<B>, _io: &mut IO, x: usize, kk: u8) -> Result<ProgramCounter> {
         Ok(ProgramCounter::skip_if(self.v[x] != kk))
     }
 
-    fn op_se(&mut self, _bus: &mut Bus, _io: &mut IO, x: usize, y: usize) -> Result<ProgramCounter> {
+    fn op_se<B: Bus>(&mut self, _bus: &mut Board<B>, _io: &mut IO, x: usize, y: usize) -> Result<ProgramCounter> {
         Ok(ProgramCounter::skip_if(self.v[x] == self.v[y]))
     }
 
-    fn op_movi(&mut self, _bus: &mut Bus, _io: &mut IO, x: usize, kk: u8) -> Result<ProgramCounter> {
+    fn op_movi<B: Bus>(&mut self, _bus: &mut Board<B>, _io: &mut IO, x: usize, kk: u8) -> Result<ProgramCounter> {
         self.v[x] = kk;
         Ok(ProgramCounter::Next)
     }
 
-    fn op_addi(&mut self, _bus: &mut Bus, _io: &mut IO, x: usize, kk: u8) -> Result<ProgramCounter> {
+    fn op_addi<B: Bus>(&mut self, _bus: &mut Board<B>, _io: &mut IO, x: usize, kk: u8) -> Result<ProgramCounter> {
         self.v[x] = self.v[x].wrapping_add(kk);
         Ok(ProgramCounter::Next)
     }
 
-    fn op_mov(&mut self, _bus: &mut Bus, _io: &mut IO, x: usize, y: usize) -> Result<ProgramCounter> {
+    fn op_mov<B: Bus>(&mut self, _bus: &mut Board<B>, _io: &mut IO, x: usize, y: usize) -> Result<ProgramCounter> {
         self.v[x] = self.v[y];
         Ok(ProgramCounter::Next)
     }
 
-    fn op_or(&mut self, _bus: &mut Bus, _io: &mut IO, x: usize, y: usize) -> Result<ProgramCounter> {
+    fn op_or<B: Bus>(&mut self, _bus: &mut Board<B>, _io: &mut IO, x: usize, y: usize) -> Result<ProgramCounter> {
         self.v[x] |= self.v[y];
         Ok(ProgramCounter::Next)
     }
 
-    fn op_and(&mut self, _bus: &mut Bus, _io: &mut IO, x: usize, y: usize) -> Result<ProgramCounter> {
+    fn op_and<B: Bus>(&mut self, _bus: &mut Board<B>, _io: &mut IO, x: usize, y: usize) -> Result<ProgramCounter> {
         self.v[x] &= self.v[y];
         Ok(ProgramCounter::Next)
     }
 
-    fn op_xor(&mut self, _bus: &mut Bus, _io: &mut IO, x: usize, y: usize) -> Result<ProgramCounter> {
+    fn op_xor<B: Bus>(&mut self, _bus: &mut Board<B>, _io: &mut IO, x: usize, y: usize) -> Result<ProgramCounter> {
         self.v[x] ^= self.v[y];
         Ok(ProgramCounter::Next)
     }
 
-    fn op_add(&mut self, _bus: &mut Bus, _io: &mut IO, x: usize, y: usize) -> Result<ProgramCounter> {
+    fn op_add<B: Bus>(&mut self, _bus: &mut Board<B>, _io: &mut IO, x: usize, y: usize) -> Result<ProgramCounter> {
         let (vx, vf) = self.v[x].overflowing_add(self.v[y]);
         self.v[x] = vx;
         self.v[0xf] = if vf { 0x01 } else { 0x00 };
         Ok(ProgramCounter::Next)
     }
 
-    fn op_sub(&mut self, _bus: &mut Bus, _io: &mut IO, x: usize, y: usize) -> Result<ProgramCounter> {
+    fn op_sub<B: Bus>(&mut self, _bus: &mut Board<B>, _io: &mut IO, x: usize, y: usize) -> Result<ProgramCounter> {
         let vf = if self.v[x] > self.v[y] { 0x01 } else { 0x00 };
         self.v[x] = self.v[x].wrapping_sub(self.v[y]);
         self.v[0xf] = vf;
         Ok(ProgramCounter::Next)
     }
 
-    fn op_shr(&mut self, _bus: &mut Bus, _io: &mut IO, x: usize, _y: usize) -> Result<ProgramCounter> {
+    fn op_shr<B: Bus>(&mut self, _bus: &mut Board<B>, _io: &mut IO, x: usize, _y: usize) -> Result<ProgramCounter> {
         let vf = if self.v[x] & 0x01 != 0 { 0x01 } else { 0x00 };
         self.v[x] >>= 1;
         self.v[0xf] = vf;
         Ok(ProgramCounter::Next)
     }
 
-    fn op_subn(&mut self, _bus: &mut Bus, _io: &mut IO, x: usize, y: usize) -> Result<ProgramCounter> {
+    fn op_subn<B: Bus>(&mut self, _bus: &mut Board<B>, _io: &mut IO, x: usize, y: usize) -> Result<ProgramCounter> {
         let vf = if self.v[y] > self.v[x] { 0x01 } else { 0x00 };
         self.v[x] = self.v[y].wrapping_sub(self.v[x]);
         self.v[0xf] = vf;
         Ok(ProgramCounter::Next)
     }
 
-    fn op_shl(&mut self, _bus: &mut Bus, _io: &mut IO, x: usize, _y: usize) -> Result<ProgramCounter> {
+    fn op_shl<B: Bus>(&mut self, _bus: &mut Board<B>, _io: &mut IO, x: usize, _y: usize) -> Result<ProgramCounter> {
         let vf = if self.v[x] & 0x80 != 0 { 0x01 } else { 0x00 };
         self.v[x] <<= 1;
         self.v[0xf] = vf;
         Ok(ProgramCounter::Next)
     }
 
-    fn op_sne(&mut self, _bus: &mut Bus, _io: &mut IO, x: usize, y: usize) -> Result<ProgramCounter> {
+    fn op_sne<B: Bus>(&mut self, _bus: &mut Board<B>, _io: &mut IO, x: usize, y: usize) -> Result<ProgramCounter> {
         Ok(ProgramCounter::skip_if(self.v[x] != self.v[y]))
     }
 
-    fn op_lea(&mut self, _bus: &mut Bus, _io: &mut IO, nnn: u16) -> Result<ProgramCounter> {
+    fn op_lea<B: Bus>(&mut self, _bus: &mut Board<B>, _io: &mut IO, nnn: u16) -> Result<ProgramCounter> {
         self.i = nnn;
         Ok(ProgramCounter::Next)
     }
 
-    fn op_jmpshort(&mut self, _bus: &mut Bus, _io: &mut IO, nnn: u16) -> Result<ProgramCounter> {
+    fn op_jmpshort<B: Bus>(&mut self, _bus: &mut Board<B>, _io: &mut IO, nnn: u16) -> Result<ProgramCounter> {
         let addr = nnn.wrapping_add(self.v[0] as u16);
         Ok(ProgramCounter::Jump(addr))
     }
 
-    fn op_rnd(&mut self, bus: &mut Bus, _io: &mut IO, x: usize, kk: u8) -> Result<ProgramCounter> {
-        self.v[x] = bus.rng.get_byte() & kk;
+    fn op_rnd<B: Bus>(&mut self, bus: &mut Board<B>, _io: &mut IO, x: usize, kk: u8) -> Result<ProgramCounter> {
+        self.v[x] = bus.rng.next_byte() & kk;
         Ok(ProgramCounter::Next)
     }
 
-    fn op_drw(&mut self, bus: &mut Bus, io: &mut IO, x: usize, y: usize, n: u8) -> Result<ProgramCounter> {
+    fn op_drw<B: Bus>(&mut self, bus: &mut Board<B>, io: &mut IO, x: usize, y: usize, n: u8) -> Result<ProgramCounter> {
         let vf = (0..n).try_fold(0x00, |acc, i| {
             let addr = self.i.wrapping_add(i as u16);
-            bus.ram.read(addr).map(|byte| {
+            bus.mark(addr, Access::Sprite);
+            bus.mem.read(addr).map(|byte| {
                 if io.screen.draw(self.v[x], self.v[y].wrapping_add(i), byte) {
                     0x01
                 } else {
@@ -255,7 +412,7 @@ impl Cpu {
         Ok(ProgramCounter::Next)
     }
 
-    fn op_skp(&mut self, _bus: &mut Bus, io: &mut IO, x: usize) -> Result<ProgramCounter> {
+    fn op_skp<B: Bus>(&mut self, _bus: &mut Board<B>, io: &mut IO, x: usize) -> Result<ProgramCounter> {
         if (self.v[x] as usize) < io.pad.len() {
             Ok(ProgramCounter::skip_if(io.pad[self.v[x] as usize]))
         } else {
@@ -263,7 +420,7 @@ impl Cpu {
         }
     }
 
-    fn op_sknp(&mut self, _bus: &mut Bus, io: &mut IO, x: usize) -> Result<ProgramCounter> {
+    fn op_sknp<B: Bus>(&mut self, _bus: &mut Board<B>, io: &mut IO, x: usize) -> Result<ProgramCounter> {
         if (self.v[x] as usize) < io.pad.len() {
             Ok(ProgramCounter::skip_if(!io.pad[self.v[x] as usize]))
         } else {
@@ -271,65 +428,78 @@ impl Cpu {
         }
     }
 
-    fn op_get_dt(&mut self, bus: &mut Bus, _io: &mut IO, x: usize) -> Result<ProgramCounter> {
+    fn op_get_dt<B: Bus>(&mut self, bus: &mut Board<B>, _io: &mut IO, x: usize) -> Result<ProgramCounter> {
         self.v[x] = bus.dt.get();
         Ok(ProgramCounter::Next)
     }
 
-    fn op_wait(&mut self, _bus: &mut Bus, io: &mut IO, x: usize) -> Result<ProgramCounter> {
-        let any_pressed = io
-            .pad
-            .iter()
-            .position(|&key| key)
-            .map(|idx| {
-                self.v[x] = idx as u8;
-            })
-            .is_some();
+    fn op_wait<B: Bus>(&mut self, _bus: &mut Board<B>, _io: &mut IO, x: usize) -> Result<ProgramCounter> {
+        // Keys already down when the wait starts are ignored
+        if !self.waiting {
+            self.waiting = true;
+            self.held = None;
+            self.pressed = 0x0000;
+            self.released = 0x0000;
+            return Ok(ProgramCounter::Wait);
+        }
+
+        let key = match self.wait_mode {
+            WaitMode::Press => (self.pressed != 0x0000).then(|| self.pressed.trailing_zeros() as u8),
+            WaitMode::Release | WaitMode::Vip => self.held.filter(|key| (self.released & (1 << key)) != 0),
+        };
 
-        Ok(ProgramCounter::wait_if(!any_pressed))
+        match key {
+            Some(key) => {
+                self.v[x] = key;
+                self.waiting = false;
+                Ok(ProgramCounter::Next)
+            }
+            None => Ok(ProgramCounter::Wait),
+        }
     }
 
-    fn op_set_dt(&mut self, bus: &mut Bus, _io: &mut IO, x: usize) -> Result<ProgramCounter> {
+    fn op_set_dt<B: Bus>(&mut self, bus: &mut Board<B>, _io: &mut IO, x: usize) -> Result<ProgramCounter> {
         bus.dt.set(self.v[x]);
         Ok(ProgramCounter::Next)
     }
 
-    fn op_set_st(&mut self, bus: &mut Bus, _io: &mut IO, x: usize) -> Result<ProgramCounter> {
+    fn op_set_st<B: Bus>(&mut self, bus: &mut Board<B>, _io: &mut IO, x: usize) -> Result<ProgramCounter> {
         bus.st.set(self.v[x]);
         Ok(ProgramCounter::Next)
     }
 
-    fn op_inc(&mut self, _bus: &mut Bus, _io: &mut IO, x: usize) -> Result<ProgramCounter> {
+    fn op_inc<B: Bus>(&mut self, _bus: &mut Board<B>, _io: &mut IO, x: usize) -> Result<ProgramCounter> {
         self.i = self.i.wrapping_add(self.v[x] as u16);
         Ok(ProgramCounter::Next)
     }
 
-    fn op_ldfont(&mut self, _bus: &mut Bus, _io: &mut IO, x: usize) -> Result<ProgramCounter> {
+    fn op_ldfont<B: Bus>(&mut self, _bus: &mut Board<B>, _io: &mut IO, x: usize) -> Result<ProgramCounter> {
         self.i = self.ft.wrapping_add((self.v[x] as u16) * FONT_SIZE);
         Ok(ProgramCounter::Next)
     }
 
-    fn op_bcd(&mut self, bus: &mut Bus, _io: &mut IO, x: usize) -> Result<ProgramCounter> {
+    fn op_bcd<B: Bus>(&mut self, bus: &mut Board<B>, _io: &mut IO, x: usize) -> Result<ProgramCounter> {
         let digits = [(self.v[x] / 100) % 10, (self.v[x] / 10) % 10, self.v[x] % 10];
 
         for (i, digit) in digits.iter().copied().enumerate() {
-            bus.ram.write(self.i.wrapping_add(i as u16), digit)?;
+            bus.write(self.pc, self.i.wrapping_add(i as u16), digit)?;
         }
 
         Ok(ProgramCounter::Next)
     }
 
-    fn op_pusha(&mut self, bus: &mut Bus, _io: &mut IO, x: usize) -> Result<ProgramCounter> {
+    fn op_pusha<B: Bus>(&mut self, bus: &mut Board<B>, _io: &mut IO, x: usize) -> Result<ProgramCounter> {
         for i in 0..=x {
-            bus.ram.write(self.i.wrapping_add(i as u16), self.v[i])?;
+            bus.write(self.pc, self.i.wrapping_add(i as u16), self.v[i])?;
         }
 
         Ok(ProgramCounter::Next)
     }
 
-    fn op_popa(&mut self, bus: &mut Bus, _io: &mut IO, x: usize) -> Result<ProgramCounter> {
+    fn op_popa<B: Bus>(&mut self, bus: &mut Board<B>, _io: &mut IO, x: usize) -> Result<ProgramCounter> {
         for i in 0..=x {
-            self.v[i] = bus.ram.read(self.i.wrapping_add(i as u16))?;
+            self.v[i] = bus.mem.read(self.i.wrapping_add(i as u16))?;
+            bus.mark(self.i.wrapping_add(i as u16), Access::Read);
         }
 
         Ok(ProgramCounter::Next)
@@ -363,12 +533,4 @@ impl ProgramCounter {
             Self::Next
         }
     }
-
-    pub fn wait_if(cond: bool) -> Self {
-        if cond {
-            Self::Wait
-        } else {
-            Self::Next
-        }
-    }
 }
@@ -36,6 +36,15 @@ impl Crc16 {
         self.state = (self.state << 8) ^ LOOKUP_TABLE[((self.state >> 8) ^ (byte as u16)) as usize];
     }
 
+    // Wider values are fed little endian, so checksums do not depend on the host
+    pub fn update_u16(&mut self, value: u16) {
+        value.to_le_bytes().into_iter().for_each(|byte| self.update(byte));
+    }
+
+    pub fn update_u64(&mut self, value: u64) {
+        value.to_le_bytes().into_iter().for_each(|byte| self.update(byte));
+    }
+
     pub fn finish(self) -> u16 {
         self.state
     }
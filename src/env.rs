@@ -0,0 +1,221 @@
+use crate::error::Error;
+use crate::io::{Framebuffer, Screen, IO};
+use crate::random::{Pcg, RandomSource};
+use crate::{unpack_keys, Chip8};
+
+// Sticky actions draw from their own stream, so they never mirror the game generator when both are PCGs
+const STICKY_SEED_MIX: u16 = 0x9e37;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Condition {
+    Equal(u16, u8),
+    NotEqual(u16, u8),
+    Below(u16, u8),
+    Above(u16, u8),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Reward {
+    // Scaled change of a byte, like a score counter, wrapping around
+    Delta(u16, f32),
+    // Fixed reward for each frame the condition holds
+    When(Condition, f32),
+}
+
+// Reinforcement learning environment, actions are bitmasks of held keys and observations are the screen
+#[derive(Debug)]
+pub struct Env {
+    rom: Vec<u8>,
+    freq: Option<f32>,
+    ipf: Option<u32>,
+    chip8: Chip8,
+    screen: Framebuffer,
+    rewards: Vec<Reward>,
+    values: Vec<u8>,
+    done: Vec<Condition>,
+    frame_skip: u32,
+    sticky: f32,
+    rng: Pcg,
+    action: u16,
+}
+
+impl Condition {
+    fn holds(&self, chip8: &Chip8) -> Result<bool, Error> {
+        let view = chip8.view();
+
+        Ok(match *self {
+            Self::Equal(addr, value) => view.peek(addr)? == value,
+            Self::NotEqual(addr, value) => view.peek(addr)? != value,
+            Self::Below(addr, value) => view.peek(addr)? < value,
+            Self::Above(addr, value) => view.peek(addr)? > value,
+        })
+    }
+}
+
+impl Env {
+    pub fn new(rom: &[u8], freq: Option<f32>) -> Result<Self, Error> {
+        let chip8 = Chip8::new(freq);
+        let (width, height) = chip8.get_screen_size();
+
+        let mut env = Self {
+            rom: rom.to_vec(),
+            freq,
+            ipf: None,
+            chip8,
+            screen: Framebuffer::new(width, height)?,
+            rewards: Vec::new(),
+            values: Vec::new(),
+            done: Vec::new(),
+            frame_skip: 1,
+            sticky: 0.0,
+            rng: Pcg::new(),
+            action: 0x0000,
+        };
+
+        env.reset(None)?;
+
+        Ok(env)
+    }
+
+    pub fn set_ipf(&mut self, ipf: u32) -> Result<(), Error> {
+        self.chip8.set_ipf(ipf)?;
+        self.ipf = Some(ipf);
+        Ok(())
+    }
+
+    // Frames emulated per step, with the same action held
+    pub fn set_frame_skip(&mut self, frames: u32) -> Result<(), Error> {
+        if frames == 0 {
+            return Err(Error::InvalidFrameSkip(frames));
+        }
+
+        self.frame_skip = frames;
+        Ok(())
+    }
+
+    pub fn get_frame_skip(&self) -> u32 {
+        self.frame_skip
+    }
+
+    // Probability for each frame to repeat the previous action instead of the requested one
+    pub fn set_sticky_actions(&mut self, probability: f32) -> Result<(), Error> {
+        if !(0.0..=1.0).contains(&probability) {
+            return Err(Error::InvalidProbability(probability));
+        }
+
+        self.sticky = probability;
+        Ok(())
+    }
+
+    pub fn get_sticky_actions(&self) -> f32 {
+        self.sticky
+    }
+
+    pub fn add_reward(&mut self, reward: Reward) -> Result<(), Error> {
+        let value = match reward {
+            Reward::Delta(addr, _) => self.chip8.view().peek(addr)?,
+            Reward::When(condition, _) => {
+                condition.holds(&self.chip8)?;
+                0x00
+            }
+        };
+
+        self.rewards.push(reward);
+        self.values.push(value);
+        Ok(())
+    }
+
+    pub fn add_done(&mut self, condition: Condition) -> Result<(), Error> {
+        condition.holds(&self.chip8)?;
+        self.done.push(condition);
+        Ok(())
+    }
+
+    // Restarts the ROM on a fresh machine, the seed also drives sticky actions through a separate mix
+    pub fn reset(&mut self, seed: Option<u16>) -> Result<&Framebuffer, Error> {
+        let mut chip8 = Chip8::new(self.freq);
+
+        if let Some(ipf) = self.ipf {
+            chip8.set_ipf(ipf)?;
+        }
+
+        chip8.load_rom(&self.rom, seed)?;
+        self.rng.seed(chip8.get_seed() ^ STICKY_SEED_MIX);
+
+        self.chip8 = chip8;
+        self.screen.clear();
+        self.action = 0x0000;
+
+        for (reward, value) in self.rewards.iter().zip(self.values.iter_mut()) {
+            if let Reward::Delta(addr, _) = reward {
+                *value = self.chip8.view().peek(*addr)?;
+            }
+        }
+
+        Ok(&self.screen)
+    }
+
+    // Stops early when a done condition holds, rewards of all emulated frames are summed
+    pub fn step(&mut self, action: u16) -> Result<(&Framebuffer, f32, bool), Error> {
+        let mut reward = 0.0;
+        let mut done = false;
+
+        for _ in 0..self.frame_skip {
+            if self.sticky == 0.0 || (self.rng.next_u32() as f64) >= (self.sticky as f64) * 4294967296.0 {
+                self.action = action;
+            }
+
+            self.chip8.frame(&mut IO {
+                screen: &mut self.screen,
+                pad: &unpack_keys(self.action),
+                audio: &mut false,
+            })?;
+
+            reward += self.get_reward()?;
+            done = self.is_done()?;
+
+            if done {
+                break;
+            }
+        }
+
+        Ok((&self.screen, reward, done))
+    }
+
+    pub fn get_observation(&self) -> &Framebuffer {
+        &self.screen
+    }
+
+    pub fn get_chip8(&self) -> &Chip8 {
+        &self.chip8
+    }
+
+    fn get_reward(&mut self) -> Result<f32, Error> {
+        let mut total = 0.0;
+
+        for (reward, value) in self.rewards.iter().zip(self.values.iter_mut()) {
+            total += match *reward {
+                Reward::Delta(addr, scale) => {
+                    let next = self.chip8.view().peek(addr)?;
+                    let delta = next.wrapping_sub(*value) as i8;
+                    *value = next;
+                    delta as f32 * scale
+                }
+                Reward::When(condition, reward) if condition.holds(&self.chip8)? => reward,
+                Reward::When(..) => 0.0,
+            };
+        }
+
+        Ok(total)
+    }
+
+    fn is_done(&self) -> Result<bool, Error> {
+        for condition in &self.done {
+            if condition.holds(&self.chip8)? {
+                return Ok(true);
+            }
+        }
+
+        Ok(false)
+    }
+}
@@ -1,38 +1,168 @@
-use std::fmt;
+use core::fmt;
 
 #[derive(Debug)]
 pub enum Error {
+    InstanceOutOfRange(usize),
+    InvalidBufferSize(usize, usize),
+    InvalidFrameSkip(u32),
+    InvalidFrequency(f32),
+    InvalidInputDelay(u32),
+    InvalidInstructionsPerFrame(u32),
+    InvalidMapping(u16, u16),
     InvalidPadSize(usize, usize),
+    InvalidProbability(f32),
+    InvalidSampleRate(u32),
     InvalidScreenSize((usize, usize), (usize, usize)),
+    InvalidState,
+    InvalidThreadCount(usize),
+    InvalidTimeScale(f64),
+    InvalidMovie,
+    MovieDesync(usize),
+    MovieFinished(usize),
+    MovieInProgress,
+    MovieRomMismatch(u16, u16),
+    MovieRngMismatch(u8, u8),
+    MovieStartedLate,
+    NetplayConfigMismatch(u16, u16),
+    NetplayDesync(u64),
+    #[cfg(feature = "std")]
+    Network(std::io::ErrorKind),
     PadOutOfRange(u8),
     RamOutOfRange(u16),
+    RegisterOutOfRange(usize),
     StackOverflow,
+    StateConfigMismatch,
+    StateRomMismatch(u16, u16),
     UndefinedInstruction([u8; 4]),
+    UnsupportedScreenSize((usize, usize)),
 }
 
 impl fmt::Display for Error {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
+            Self::InstanceOutOfRange(index) => {
+                write!(f, "Instance {} is invalid", index)
+            }
+            Self::InvalidBufferSize(size, expected) => {
+                write!(f, "Buffer size is {}, size {} is expected", size, expected)
+            }
+            Self::InvalidFrameSkip(frames) => {
+                write!(f, "Frame skip of {} frames is invalid", frames)
+            }
+            Self::InvalidFrequency(freq) => {
+                write!(f, "CPU frequency of {} Hz is invalid", freq)
+            }
+            Self::InvalidInputDelay(delay) => {
+                write!(f, "Input delay of {} frames is invalid", delay)
+            }
+            Self::InvalidInstructionsPerFrame(ipf) => {
+                write!(f, "{} instructions per frame is invalid", ipf)
+            }
+            Self::InvalidMapping(start, end) => {
+                write!(
+                    f,
+                    "Device range 0x{:04x}..0x{:04x} is empty or already mapped",
+                    start, end
+                )
+            }
             Self::InvalidPadSize(size, supported) => {
                 write!(f, "Pad size is {}, only size {} is supported", size, supported)
             }
+            Self::InvalidProbability(probability) => {
+                write!(f, "Probability of {} is invalid", probability)
+            }
+            Self::InvalidSampleRate(rate) => {
+                write!(f, "Sample rate of {} Hz is invalid", rate)
+            }
             Self::InvalidScreenSize(size, supported) => {
                 write!(f, "Screen size is {:?}, only size {:?} is supported", size, supported)
             }
-            Self::PadOutOfRange(addr) => {
-                write!(f, "RAM address 0x{:04x} is invalid", addr)
+            Self::InvalidState => {
+                write!(f, "Save state is invalid")
+            }
+            Self::InvalidThreadCount(threads) => {
+                write!(f, "Thread count of {} is invalid", threads)
+            }
+            Self::InvalidTimeScale(scale) => {
+                write!(f, "Time scale of {} is invalid", scale)
+            }
+            Self::InvalidMovie => {
+                write!(f, "Movie file is invalid")
+            }
+            Self::MovieDesync(frame) => {
+                write!(f, "Movie playback desynchronized at frame {}", frame)
+            }
+            Self::MovieFinished(frame) => {
+                write!(f, "Movie playback finished after {} frames", frame)
+            }
+            Self::MovieInProgress => {
+                write!(f, "Operation is not allowed while a movie is running")
+            }
+            Self::MovieRomMismatch(expected, found) => {
+                write!(
+                    f,
+                    "Movie expects ROM with CRC 0x{:04x}, loaded ROM has CRC 0x{:04x}",
+                    expected, found
+                )
+            }
+            Self::MovieRngMismatch(expected, found) => {
+                write!(
+                    f,
+                    "Movie expects random generator 0x{:02x}, machine uses generator 0x{:02x}",
+                    expected, found
+                )
+            }
+            Self::MovieStartedLate => {
+                write!(f, "Movie must start before the first instruction is executed")
+            }
+            Self::NetplayConfigMismatch(local, remote) => {
+                write!(
+                    f,
+                    "Netplay peers run different configurations, local hash 0x{:04x}, remote hash 0x{:04x}",
+                    local, remote
+                )
+            }
+            Self::NetplayDesync(frame) => {
+                write!(f, "Netplay desynchronized at frame {}", frame)
+            }
+            #[cfg(feature = "std")]
+            Self::Network(kind) => {
+                write!(f, "Network error: {}", kind)
+            }
+            Self::PadOutOfRange(key) => {
+                write!(f, "Pad key 0x{:02x} is invalid", key)
             }
             Self::RamOutOfRange(addr) => {
-                write!(f, "Pad address 0x{:02x} is invalid", addr)
+                write!(f, "RAM address 0x{:04x} is invalid", addr)
+            }
+            Self::RegisterOutOfRange(x) => {
+                write!(f, "Register V{:x} is invalid", x)
             }
             Self::StackOverflow => {
                 write!(f, "CPU Stack overflow")
             }
+            Self::StateConfigMismatch => {
+                write!(
+                    f,
+                    "Save state was made with another frequency or instructions per frame"
+                )
+            }
+            Self::StateRomMismatch(expected, found) => {
+                write!(
+                    f,
+                    "Save state expects ROM with CRC 0x{:04x}, loaded ROM has CRC 0x{:04x}",
+                    expected, found
+                )
+            }
             Self::UndefinedInstruction(op) => {
                 write!(f, "Opcode {:02x}{:02x}{:02x}{:02x}", op[0], op[1], op[2], op[3])
             }
+            Self::UnsupportedScreenSize(size) => {
+                write!(f, "Screen size {:?} is unsupported", size)
+            }
         }
     }
 }
 
+#[cfg(feature = "std")]
 impl std::error::Error for Error {}
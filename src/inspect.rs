@@ -0,0 +1,117 @@
+use crate::bus::Bus;
+use crate::error::Error;
+#[cfg(not(feature = "std"))]
+use crate::random::RandomSource;
+use crate::Chip8;
+
+// Read-only access to the machine state
+// Part of the stable API: accessors keep their signature and meaning across minor releases, new ones may be added
+#[derive(Debug)]
+pub struct View<'a, B> {
+    chip8: &'a Chip8<B>,
+}
+
+// Controlled mutation for debuggers and cheat engines, with the same stability guarantees as View
+#[derive(Debug)]
+pub struct Editor<'a, B> {
+    chip8: &'a mut Chip8<B>,
+}
+
+impl<'a, B: Bus> View<'a, B> {
+    pub(crate) fn new(chip8: &'a Chip8<B>) -> Self {
+        Self { chip8 }
+    }
+
+    pub fn get_v(&self) -> [u8; 0x10] {
+        self.chip8.cpu.get_v()
+    }
+
+    pub fn get_i(&self) -> u16 {
+        self.chip8.cpu.get_i()
+    }
+
+    pub fn get_pc(&self) -> u16 {
+        self.chip8.cpu.get_pc()
+    }
+
+    pub fn get_stack(&self) -> &'a [u16] {
+        self.chip8.cpu.get_stack()
+    }
+
+    pub fn get_dt(&self) -> u8 {
+        self.chip8.bus.dt.get()
+    }
+
+    pub fn get_st(&self) -> u8 {
+        self.chip8.bus.st.get()
+    }
+
+    pub fn get_rng_state(&self) -> u64 {
+        self.chip8.bus.rng.get_state()
+    }
+
+    pub fn get_cycles(&self) -> u64 {
+        self.chip8.cycles
+    }
+
+    pub fn peek(&self, addr: u16) -> Result<u8, Error> {
+        self.chip8.bus.mem.peek(addr)
+    }
+
+    pub fn read(&self, addr: u16, buf: &mut [u8]) -> Result<(), Error> {
+        buf.iter_mut().try_fold(addr, |addr, byte| {
+            *byte = self.peek(addr)?;
+            Ok(addr.wrapping_add(1))
+        })?;
+
+        Ok(())
+    }
+}
+
+impl<'a, B: Bus> Editor<'a, B> {
+    pub(crate) fn new(chip8: &'a mut Chip8<B>) -> Self {
+        Self { chip8 }
+    }
+
+    pub fn view(&self) -> View<'_, B> {
+        View::new(self.chip8)
+    }
+
+    pub fn set_v(&mut self, x: usize, value: u8) -> Result<(), Error> {
+        self.chip8.cpu.set_v(x, value)
+    }
+
+    pub fn set_i(&mut self, i: u16) {
+        self.chip8.cpu.set_i(i);
+    }
+
+    pub fn set_pc(&mut self, pc: u16) {
+        self.chip8.cpu.set_pc(pc);
+    }
+
+    pub fn set_stack(&mut self, stack: &[u16]) -> Result<(), Error> {
+        self.chip8.cpu.set_stack(stack)
+    }
+
+    pub fn set_dt(&mut self, value: u8) {
+        self.chip8.bus.dt.set(value);
+    }
+
+    pub fn set_st(&mut self, value: u8) {
+        self.chip8.bus.st.set(value);
+    }
+
+    pub fn set_rng_state(&mut self, state: u64) {
+        self.chip8.bus.rng.set_state(state);
+    }
+
+    // Patches of already executed code are reported like self-modifying code
+    pub fn write(&mut self, addr: u16, bytes: &[u8]) -> Result<(), Error> {
+        bytes.iter().copied().try_fold(addr, |addr, byte| {
+            self.chip8.bus.poke(addr, byte)?;
+            Ok(addr.wrapping_add(1))
+        })?;
+
+        Ok(())
+    }
+}
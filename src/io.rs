@@ -1,7 +1,18 @@
+mod framebuffer;
 mod screen;
 
+pub use framebuffer::Framebuffer;
+pub use screen::Damage;
+pub use screen::Rect;
 pub use screen::Screen;
 
+// Pad index of a key that went down or up, for hosts seeing input edges between clocks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyEvent {
+    Press(usize),
+    Release(usize),
+}
+
 #[derive(Debug)]
 pub struct IO<'a> {
     pub screen: &'a mut dyn Screen,
@@ -0,0 +1,163 @@
+use crate::error;
+use crate::io::screen::{Damage, Rect, Screen};
+use crate::SCREEN_SIZE;
+
+// Rows are packed with the leftmost pixel in the least significant bit
+#[derive(Debug, Clone)]
+pub struct Framebuffer {
+    rows: [u128; Framebuffer::MAX_HEIGHT],
+    width: usize,
+    height: usize,
+    damage: Damage,
+}
+
+impl Framebuffer {
+    pub const MAX_WIDTH: usize = u128::BITS as usize;
+    pub const MAX_HEIGHT: usize = 64;
+
+    pub fn new(width: usize, height: usize) -> Result<Self, error::Error> {
+        // Screens at least as wide as a sprite keep wrapped sprites to a single row word
+        if !(8..=Self::MAX_WIDTH).contains(&width) || !(1..=Self::MAX_HEIGHT).contains(&height) {
+            return Err(error::Error::UnsupportedScreenSize((width, height)));
+        }
+
+        Ok(Self {
+            rows: [0; Self::MAX_HEIGHT],
+            width,
+            height,
+            damage: Damage::new(),
+        })
+    }
+
+    // Switching resolution blanks the screen
+    pub fn resize(&mut self, width: usize, height: usize) -> Result<(), error::Error> {
+        *self = Self::new(width, height)?;
+        self.invalidate();
+        Ok(())
+    }
+
+    pub fn get_row(&self, y: usize) -> u128 {
+        self.rows[y]
+    }
+
+    pub fn get_rows(&self) -> &[u128] {
+        &self.rows[..self.height]
+    }
+
+    pub fn is_changed(&self) -> bool {
+        self.damage.is_changed()
+    }
+
+    pub fn take_damage(&mut self) -> Option<Rect> {
+        self.damage.take()
+    }
+
+    pub fn invalidate(&mut self) {
+        self.damage.add(Rect::new(0, 0, self.width, self.height));
+    }
+
+    #[cfg(feature = "std")]
+    pub fn to_bools(&self) -> Vec<bool> {
+        let mut pixels = vec![false; self.width * self.height];
+        self.write_bools(&mut pixels);
+        pixels
+    }
+
+    // Row major, the output must hold at least width * height pixels
+    pub fn write_bools(&self, out: &mut [bool]) {
+        for (row, line) in self.get_rows().iter().zip(out.chunks_mut(self.width)) {
+            for (x, px) in line.iter_mut().enumerate() {
+                *px = (row >> x) & 1 != 0;
+            }
+        }
+    }
+
+    #[cfg(feature = "std")]
+    pub fn to_rgba(&self, fg: [u8; 4], bg: [u8; 4]) -> Vec<u8> {
+        let mut pixels = vec![0x00; self.width * self.height * 4];
+        self.write_rgba(&mut pixels, fg, bg);
+        pixels
+    }
+
+    // Row major, the output must hold at least width * height * 4 bytes
+    pub fn write_rgba(&self, out: &mut [u8], fg: [u8; 4], bg: [u8; 4]) {
+        for (row, line) in self.get_rows().iter().zip(out.chunks_mut(self.width * 4)) {
+            for (x, px) in line.chunks_exact_mut(4).enumerate() {
+                px.copy_from_slice(if (row >> x) & 1 != 0 { &fg } else { &bg });
+            }
+        }
+    }
+
+    // One line per row, lit pixels as '#' and blank ones as '.'
+    #[cfg(feature = "std")]
+    pub fn to_text(&self) -> String {
+        let mut text = String::with_capacity((self.width + 1) * self.height);
+
+        for row in self.get_rows() {
+            text.extend((0..self.width).map(|x| if (row >> x) & 1 != 0 { '#' } else { '.' }));
+            text.push('\n');
+        }
+
+        text
+    }
+
+    fn mask(&self) -> u128 {
+        u128::MAX >> (Self::MAX_WIDTH - self.width)
+    }
+}
+
+impl Default for Framebuffer {
+    fn default() -> Self {
+        Self::new(SCREEN_SIZE.0, SCREEN_SIZE.1).unwrap()
+    }
+}
+
+impl Screen for Framebuffer {
+    fn get_width(&self) -> usize {
+        self.width
+    }
+
+    fn get_height(&self) -> usize {
+        self.height
+    }
+
+    fn get_pixel(&self, x: usize, y: usize) -> bool {
+        (self.rows[y] >> x) & 1 != 0
+    }
+
+    fn set_pixel(&mut self, x: usize, y: usize, on: bool) {
+        if self.get_pixel(x, y) != on {
+            self.rows[y] ^= 1 << x;
+            self.damage.add(Rect::new(x, y, 1, 1));
+        }
+    }
+
+    fn clear(&mut self) {
+        if self.get_rows().iter().any(|row| *row != 0) {
+            self.rows.fill(0);
+            self.invalidate();
+        }
+    }
+
+    fn draw(&mut self, x: u8, y: u8, byte: u8) -> bool {
+        if byte == 0x00 {
+            return false;
+        }
+
+        let x_wrapped = (x as usize) % self.width;
+        let y_wrapped = (y as usize) % self.height;
+
+        // Sprite bits are stored MSB first, reverse them so the leftmost pixel is bit 0
+        let sprite = byte.reverse_bits() as u128;
+        let sprite =
+            ((sprite << x_wrapped) | sprite.checked_shr((self.width - x_wrapped) as u32).unwrap_or(0)) & self.mask();
+
+        let row = &mut self.rows[y_wrapped];
+        let erased = *row & sprite != 0;
+        *row ^= sprite;
+
+        self.damage.add(Rect::sprite(x, y, self.width, self.height));
+
+        erased
+    }
+}
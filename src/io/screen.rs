@@ -1,35 +1,61 @@
-use std::fmt;
+use core::fmt;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Rect {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Damage {
+    rect: Option<Rect>,
+}
 
 pub trait Screen {
-    fn as_slice(&self) -> &[bool];
-    fn as_mut_slice(&mut self) -> &mut [bool];
     fn get_width(&self) -> usize;
     fn get_height(&self) -> usize;
+    fn get_pixel(&self, x: usize, y: usize) -> bool;
+    fn set_pixel(&mut self, x: usize, y: usize, on: bool);
+
+    // Called with the area modified by clear and draw, nothing is reported when no pixel changed
+    fn damage(&mut self, _rect: Rect) {}
 
     fn clear(&mut self) {
-        for px in self.as_mut_slice() {
-            *px = false;
+        let mut changed = false;
+
+        for y in 0..self.get_height() {
+            for x in 0..self.get_width() {
+                changed |= self.get_pixel(x, y);
+                self.set_pixel(x, y, false);
+            }
+        }
+
+        if changed {
+            self.damage(Rect::new(0, 0, self.get_width(), self.get_height()));
         }
     }
 
     fn draw(&mut self, x: u8, y: u8, byte: u8) -> bool {
         let width = self.get_width();
         let height = self.get_height();
+        let line = (y as usize) % height;
 
-        let line = self
-            .as_mut_slice()
-            .chunks_mut(width)
-            .nth((y as usize) % height)
-            .unwrap();
-
-        (0..8).fold(false, |acc, i| {
+        let erased = (0..8).fold(false, |acc, i| {
             let x = ((x as usize).wrapping_add(i)) % width;
             let px = ((byte << i) & 0x80) != 0x00;
 
-            let erased = line[x] & px;
-            line[x] ^= px;
-            acc | erased
-        })
+            let lit = self.get_pixel(x, line);
+            self.set_pixel(x, line, lit ^ px);
+            acc | (lit & px)
+        });
+
+        if byte != 0x00 {
+            self.damage(Rect::sprite(x, y, width, height));
+        }
+
+        erased
     }
 
     fn size(&self) -> (usize, usize) {
@@ -37,10 +63,65 @@ pub trait Screen {
     }
 }
 
+impl Rect {
+    pub fn new(x: usize, y: usize, width: usize, height: usize) -> Self {
+        Self { x, y, width, height }
+    }
+
+    // Area covered by a sprite line, sprites wrapping around the right edge damage the whole line
+    pub(crate) fn sprite(x: u8, y: u8, width: usize, height: usize) -> Self {
+        let x = (x as usize) % width;
+        let y = (y as usize) % height;
+
+        if x + 8 <= width {
+            Rect::new(x, y, 8, 1)
+        } else {
+            Rect::new(0, y, width, 1)
+        }
+    }
+
+    pub fn union(&self, other: &Rect) -> Rect {
+        let x = self.x.min(other.x);
+        let y = self.y.min(other.y);
+        let right = (self.x + self.width).max(other.x + other.width);
+        let bottom = (self.y + self.height).max(other.y + other.height);
+
+        Rect::new(x, y, right - x, bottom - y)
+    }
+
+    pub fn rows(&self) -> core::ops::Range<usize> {
+        self.y..(self.y + self.height)
+    }
+}
+
+impl Damage {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn add(&mut self, rect: Rect) {
+        self.rect = Some(match self.rect {
+            Some(damaged) => damaged.union(&rect),
+            None => rect,
+        });
+    }
+
+    pub fn is_changed(&self) -> bool {
+        self.rect.is_some()
+    }
+
+    pub fn get_rect(&self) -> Option<Rect> {
+        self.rect
+    }
+
+    pub fn take(&mut self) -> Option<Rect> {
+        self.rect.take()
+    }
+}
+
 impl fmt::Debug for &mut dyn Screen {
     fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
         fmt.debug_struct("dyn Screen")
-            .field("memory", &self.as_slice())
             .field("width", &self.get_width())
             .field("height", &self.get_height())
             .finish()
@@ -1,12 +1,103 @@
+#![cfg_attr(not(feature = "std"), no_std)]
+
+#[cfg(feature = "std")]
+mod analysis;
+#[cfg(feature = "std")]
+mod audio;
+#[cfg(feature = "std")]
+mod batch;
 mod bus;
 mod clock;
+#[cfg(feature = "std")]
+mod coverage;
 mod cpu;
 mod crc16;
+#[cfg(feature = "std")]
+mod env;
 mod error;
+mod inspect;
 mod io;
+#[cfg(feature = "std")]
+mod movie;
+#[cfg(feature = "std")]
+mod netplay;
+#[cfg(feature = "std")]
+mod profiler;
+mod random;
+#[cfg(feature = "std")]
+mod smc;
+mod state;
+
+use core::time::Duration;
 
+#[cfg(feature = "std")]
+pub use analysis::Analysis;
+#[cfg(feature = "std")]
+pub use analysis::BasicBlock;
+#[cfg(feature = "std")]
+pub use analysis::BlockExit;
+#[cfg(feature = "std")]
+pub use analysis::Routine;
+#[cfg(feature = "std")]
+pub use audio::AudioRenderer;
+#[cfg(feature = "std")]
+pub use audio::Timeline;
+#[cfg(feature = "std")]
+pub use batch::Batch;
+pub use bus::Access;
+pub use bus::Bus;
+#[cfg(feature = "std")]
+pub use bus::MappedBus;
+pub use bus::Ram;
+pub use bus::MEMORY_SIZE;
+pub use clock::CatchUp;
+#[cfg(feature = "std")]
+pub use clock::ManualTime;
+#[cfg(feature = "std")]
+pub use clock::RealTime;
+pub use clock::ScaledTime;
+pub use clock::TimeSource;
+#[cfg(feature = "std")]
+pub use coverage::Coverage;
+#[cfg(feature = "std")]
+pub use coverage::Region;
+pub use cpu::WaitMode;
+#[cfg(feature = "std")]
+pub use env::Condition;
+#[cfg(feature = "std")]
+pub use env::Env;
+#[cfg(feature = "std")]
+pub use env::Reward;
+pub use error::Error;
+pub use inspect::Editor;
+pub use inspect::View;
+pub use io::Damage;
+pub use io::Framebuffer;
+pub use io::KeyEvent;
+pub use io::Rect;
 pub use io::Screen;
 pub use io::IO;
+#[cfg(feature = "std")]
+pub use movie::Movie;
+#[cfg(feature = "std")]
+pub use netplay::Netplay;
+#[cfg(feature = "std")]
+pub use profiler::Profiler;
+#[cfg(feature = "std")]
+pub use profiler::Subroutine;
+pub use random::Lfsr;
+#[cfg(feature = "std")]
+pub use random::Pcg;
+pub use random::RandomSource;
+#[cfg(feature = "std")]
+pub use random::Scripted;
+#[cfg(feature = "std")]
+pub use random::Vip;
+#[cfg(feature = "std")]
+pub use smc::SmcDetector;
+#[cfg(feature = "std")]
+pub use smc::SmcEvent;
+pub use state::STATE_SIZE;
 
 // Pad and screen data
 const KEY_MAP: [(char, usize); 0x10] = [
@@ -29,12 +120,36 @@ const KEY_MAP: [(char, usize); 0x10] = [
 ];
 const SCREEN_SIZE: (usize, usize) = (64, 32);
 
+// Pads are packed into bitmasks, key 0x0 being the least significant bit
+#[cfg(feature = "std")]
+fn pack_keys(pad: &[bool]) -> u16 {
+    pad.iter()
+        .enumerate()
+        .fold(0x0000, |acc, (i, &key)| if key { acc | (1 << i) } else { acc })
+}
+
+#[cfg(feature = "std")]
+fn unpack_keys(keys: u16) -> [bool; KEY_MAP.len()] {
+    core::array::from_fn(|i| (keys >> i) & 0x1 != 0)
+}
+
 // CHIP-8 default values
 const CPU_FREQUENCY: f32 = 500.0;
 const TIMER_FREQUENCY: f32 = 60.0;
 const FONT_START: u16 = 0x0000;
 const PROGRAM_START: u16 = 0x0200;
 const RNG_SEED: u16 = 0xcafe;
+#[cfg(feature = "std")]
+const MOVIE_CHECKSUM_INTERVAL: u32 = 60;
+const CATCH_UP_BUDGET: Duration = Duration::from_millis(100);
+
+// Buzzer defaults
+#[cfg(feature = "std")]
+const AUDIO_SAMPLE_RATE: u32 = 44100;
+#[cfg(feature = "std")]
+const AUDIO_PITCH: f32 = 440.0;
+#[cfg(feature = "std")]
+const AUDIO_VOLUME: f32 = 0.25;
 
 // Pre-loaded sprites
 const FONT_SPRITES: [[u8; 5]; 0x10] = [
@@ -57,21 +172,55 @@ const FONT_SPRITES: [[u8; 5]; 0x10] = [
 ];
 
 #[derive(Debug)]
-pub struct Chip8 {
+pub struct Chip8<B = Ram> {
     cpu: cpu::Cpu,
-    bus: bus::Bus,
+    bus: bus::Board<B>,
     pad_map: [char; KEY_MAP.len()],
     screen_size: (usize, usize),
     clock_60htz: clock::Clock,
     clock_cpu: clock::Clock,
+    #[cfg(feature = "std")]
+    time: Box<dyn clock::TimeSource + Send>,
+    // Without std, the host provides the time on each clock call
+    #[cfg(not(feature = "std"))]
+    time: Duration,
+    timebase: clock::Timebase,
+    limiter: Option<clock::Limiter>,
+    freq: f32,
+    ipf: Option<u32>,
+    rom_crc: u16,
+    seed: u16,
+    cycles: u64,
+    frames: u64,
+    pad: [bool; KEY_MAP.len()],
+    #[cfg(feature = "std")]
+    timeline: Option<audio::Timeline>,
+    #[cfg(feature = "std")]
+    movie: Option<movie::Session>,
 }
 
+// Hosts running machines in bulk move them across threads
+const _: () = {
+    const fn assert_send<T: Send>() {}
+    assert_send::<Chip8>();
+};
+
 impl Chip8 {
     pub fn new(freq: Option<f32>) -> Self {
+        Self::with_bus(freq, Default::default())
+    }
+}
+
+impl<B: Bus> Chip8<B> {
+    pub fn with_bus(freq: Option<f32>, bus: B) -> Self {
         let freq = freq.unwrap_or(CPU_FREQUENCY);
+        #[cfg(feature = "std")]
+        let time = Box::new(clock::RealTime::new());
+        #[cfg(not(feature = "std"))]
+        let time = Duration::ZERO;
 
-        let mut sorted_map: Vec<_> = KEY_MAP.into();
-        sorted_map.sort_by_key(|map| map.1);
+        let mut sorted_map = KEY_MAP;
+        sorted_map.sort_unstable_by_key(|map| map.1);
 
         let mut pad_map = [Default::default(); KEY_MAP.len()];
         pad_map
@@ -81,11 +230,28 @@ impl Chip8 {
 
         Self {
             cpu: Default::default(),
-            bus: Default::default(),
+            bus: bus::Board::new(bus),
             pad_map,
             screen_size: SCREEN_SIZE,
-            clock_60htz: clock::Clock::new(std::time::Duration::from_secs(1).div_f32(TIMER_FREQUENCY)),
-            clock_cpu: clock::Clock::new(std::time::Duration::from_secs(1).div_f32(freq)),
+            clock_60htz: clock::Clock::new(TIMER_FREQUENCY),
+            clock_cpu: clock::Clock::new(freq),
+            #[cfg(feature = "std")]
+            timebase: clock::Timebase::new(time.now()),
+            #[cfg(not(feature = "std"))]
+            timebase: clock::Timebase::new(time),
+            time,
+            limiter: None,
+            freq,
+            ipf: None,
+            rom_crc: 0,
+            seed: 0,
+            cycles: 0,
+            frames: 0,
+            pad: [false; KEY_MAP.len()],
+            #[cfg(feature = "std")]
+            timeline: None,
+            #[cfg(feature = "std")]
+            movie: None,
         }
     }
 
@@ -96,7 +262,7 @@ impl Chip8 {
         // Copy sprites in memory
         FONT_SPRITES.iter().try_fold(ft, |addr, sprite| {
             sprite.iter().copied().try_fold(addr, |addr, byte| {
-                self.bus.ram.write(addr, byte)?;
+                self.bus.mem.write(addr, byte)?;
                 Ok(addr.wrapping_add(1))
             })
         })?;
@@ -106,12 +272,13 @@ impl Chip8 {
 
         rom.iter().copied().try_fold(pc, |addr, byte| {
             crc.update(byte);
-            self.bus.ram.write(addr, byte)?;
+            self.bus.mem.write(addr, byte)?;
             Ok(addr.wrapping_add(1))
         })?;
 
         // Derive seed from ROM if not provided
-        let seed = seed.unwrap_or_else(|| match crc.finish() {
+        let crc = crc.finish();
+        let seed = seed.unwrap_or(match crc {
             0x0000 => RNG_SEED,
             n => n,
         });
@@ -119,34 +286,434 @@ impl Chip8 {
         self.cpu.init(pc, ft);
         self.bus.rng.seed(seed);
 
+        self.rom_crc = crc;
+        self.seed = seed;
+
         Ok(())
     }
 
     pub fn clock(&mut self, io: &mut io::IO) -> Result<(), error::Error> {
-        if io.screen.size() != self.screen_size {
-            return Err(error::Error::InvalidScreenSize(io.screen.size(), self.screen_size));
+        self.check_io(io)?;
+
+        if !self.timebase.is_paused() {
+            let now = self.timebase.now(self.host_now());
+            self.run_until(now, io)?;
         }
 
-        if io.pad.len() != self.pad_map.len() {
-            return Err(error::Error::InvalidPadSize(io.pad.len(), self.pad_map.len()));
+        *io.audio = !self.timebase.is_paused() && self.is_beeping();
+
+        Ok(())
+    }
+
+    // Host time is monotonic and starts at zero
+    #[cfg(not(feature = "std"))]
+    pub fn clock_at(&mut self, io: &mut io::IO, now: Duration) -> Result<(), error::Error> {
+        self.time = self.time.max(now);
+        self.clock(io)
+    }
+
+    // Runs exactly one timer tick worth of emulation, and leaves emulation paused
+    pub fn step_frame(&mut self, io: &mut io::IO) -> Result<(), error::Error> {
+        self.check_io(io)?;
+        self.pause();
+
+        let now = match self.clock_60htz.next_tick() {
+            Some(next) => next,
+            None => self.timebase.now(self.host_now()),
+        };
+
+        self.timebase.advance_to(now);
+        self.run_until(now, io)?;
+
+        *io.audio = false;
+
+        Ok(())
+    }
+
+    // Runs one frame of instructions followed by a timer tick, for hosts pacing emulation themselves
+    pub fn frame(&mut self, io: &mut io::IO) -> Result<(), error::Error> {
+        self.check_io(io)?;
+        self.run_frame(io)?;
+
+        *io.audio = self.is_beeping();
+
+        Ok(())
+    }
+
+    // Runs a single instruction without ticking timers, for debuggers and automation
+    pub fn step(&mut self, io: &mut io::IO) -> Result<(), error::Error> {
+        self.check_io(io)?;
+
+        if self.has_movie() {
+            return Err(error::Error::MovieInProgress);
         }
 
-        self.clock_cpu.tick(std::time::Instant::now(), || {
-            self.cpu.cycle(&mut self.bus, io)?;
-            Ok(())
-        })?;
+        self.latch_keys(io.pad);
+        self.cpu.cycle(&mut self.bus, io)?;
+        self.cycles += 1;
 
-        self.clock_60htz.tick(std::time::Instant::now(), || {
-            self.bus.dt.clock();
-            self.bus.st.clock();
-            Ok(())
-        })?;
+        *io.audio = self.is_beeping();
+
+        Ok(())
+    }
+
+    pub fn pause(&mut self) {
+        self.timebase.pause(self.host_now());
+    }
+
+    pub fn resume(&mut self) {
+        self.timebase.resume(self.host_now());
+    }
+
+    pub fn is_paused(&self) -> bool {
+        self.timebase.is_paused()
+    }
+
+    // Scales emulated time, timers stay at 60 ticks per emulated second
+    pub fn set_speed(&mut self, speed: f64) -> Result<(), error::Error> {
+        if !speed.is_normal() || speed.is_sign_negative() {
+            return Err(error::Error::InvalidTimeScale(speed));
+        }
+
+        self.timebase.set_speed(speed, self.host_now());
+
+        Ok(())
+    }
+
+    pub fn get_speed(&self) -> f64 {
+        self.timebase.get_speed()
+    }
+
+    // Movies are recorded at a single frequency, so it cannot change while one is running
+    pub fn set_freq(&mut self, freq: f32) -> Result<(), error::Error> {
+        if !freq.is_normal() || freq.is_sign_negative() {
+            return Err(error::Error::InvalidFrequency(freq));
+        }
+
+        if self.has_movie() {
+            return Err(error::Error::MovieInProgress);
+        }
+
+        self.clock_cpu.set_freq(freq, self.timebase.now(self.host_now()));
+        self.freq = freq;
+        self.ipf = None;
+
+        Ok(())
+    }
+
+    pub fn get_freq(&self) -> f32 {
+        self.freq
+    }
+
+    // Seed the machine was last seeded with, derived from the ROM unless given
+    pub fn get_seed(&self) -> u16 {
+        self.seed
+    }
+
+    // Frame-locked mode runs exactly this many instructions per timer tick, instead of following the CPU frequency
+    pub fn set_ipf(&mut self, ipf: u32) -> Result<(), error::Error> {
+        if ipf == 0 {
+            return Err(error::Error::InvalidInstructionsPerFrame(ipf));
+        }
+
+        self.set_freq((ipf as f32) * TIMER_FREQUENCY)?;
+        self.ipf = Some(ipf);
+
+        Ok(())
+    }
+
+    pub fn get_ipf(&self) -> Option<u32> {
+        self.ipf
+    }
+
+    #[cfg(feature = "std")]
+    pub fn set_time_source<T: clock::TimeSource + Send + 'static>(&mut self, time: T) {
+        self.timebase.rebase(self.time.now(), time.now());
+        self.time = Box::new(time);
+    }
+
+    // Limits the emulated time run by a single clock call, unlimited by default
+    pub fn set_catch_up(&mut self, policy: clock::CatchUp, budget: Option<Duration>) {
+        self.limiter = Some(clock::Limiter::new(policy, budget.unwrap_or(CATCH_UP_BUDGET)));
+    }
+
+    pub fn get_catch_up(&self) -> Option<clock::CatchUp> {
+        self.limiter.as_ref().map(|limiter| limiter.get_policy())
+    }
+
+    // Cycles that could not be run in time since catch-up limiting was enabled
+    pub fn get_dropped_cycles(&self) -> u64 {
+        self.limiter
+            .as_ref()
+            .map(|limiter| limiter.get_dropped())
+            .unwrap_or_default()
+    }
+
+    // Frames still to be caught up, hosts skipping frames should not present the screen meanwhile
+    pub fn is_behind(&self) -> bool {
+        self.limiter.as_ref().is_some_and(|limiter| limiter.is_behind())
+    }
+
+    // The generator is seeded again with the current seed
+    #[cfg(feature = "std")]
+    pub fn set_rng<R: random::RandomSource + Send + 'static>(&mut self, rng: R) {
+        self.bus.rng = Box::new(rng);
+        self.bus.rng.seed(self.seed);
+    }
+
+    pub fn get_wait_mode(&self) -> cpu::WaitMode {
+        self.cpu.get_wait_mode()
+    }
+
+    pub fn set_wait_mode(&mut self, mode: cpu::WaitMode) {
+        self.cpu.set_wait_mode(mode);
+    }
+
+    // Edges shorter than a clock would be missed by pad snapshots, they are merged with the edges between
+    // snapshots so frontends may provide either. Movies only store snapshots, so events are ignored meanwhile
+    pub fn key_event(&mut self, event: io::KeyEvent) -> Result<(), error::Error> {
+        match event {
+            io::KeyEvent::Press(key) | io::KeyEvent::Release(key) if key >= self.pad_map.len() => {
+                Err(error::Error::PadOutOfRange(key as u8))
+            }
+            _ if self.has_movie() => Ok(()),
+            _ => {
+                self.cpu.key_event(event);
+                Ok(())
+            }
+        }
+    }
+
+    #[cfg(feature = "std")]
+    pub fn record_coverage(&mut self) {
+        self.bus.coverage = Some(coverage::Coverage::new());
+    }
+
+    #[cfg(feature = "std")]
+    pub fn take_coverage(&mut self) -> Option<coverage::Coverage> {
+        self.bus.coverage.take()
+    }
+
+    #[cfg(feature = "std")]
+    pub fn record_profile(&mut self) {
+        self.bus.profiler = Some(profiler::Profiler::new());
+    }
+
+    #[cfg(feature = "std")]
+    pub fn take_profile(&mut self) -> Option<profiler::Profiler> {
+        self.bus.profiler.take()
+    }
+
+    #[cfg(feature = "std")]
+    pub fn record_smc(&mut self) {
+        self.bus.smc = Some(smc::SmcDetector::new());
+    }
+
+    #[cfg(feature = "std")]
+    pub fn get_smc(&self) -> Option<&smc::SmcDetector> {
+        self.bus.smc.as_ref()
+    }
+
+    #[cfg(feature = "std")]
+    pub fn take_smc(&mut self) -> Option<smc::SmcDetector> {
+        self.bus.smc.take()
+    }
+
+    // The screen is part of the state, as sprites are drawn over its previous content
+    pub fn save_state(&self, screen: &dyn io::Screen, out: &mut [u8]) -> Result<(), error::Error> {
+        if screen.size() != self.screen_size {
+            return Err(error::Error::InvalidScreenSize(screen.size(), self.screen_size));
+        }
+
+        let mut w = state::Writer::new(out)?;
+
+        self.cpu.save(&mut w);
+        self.bus.save(&mut w);
+        w.u64(self.cycles);
+        w.u64(self.frames);
+        w.u16(self.pad.iter().rev().fold(0, |acc, down| (acc << 1) | (*down as u16)));
+        w.u16(self.seed);
+        w.u16(self.rom_crc);
+        w.u32(self.freq.to_bits());
+        w.u32(self.ipf.unwrap_or_default());
+
+        let (width, height) = screen.size();
+        for y in 0..height {
+            for x in (0..width).step_by(8) {
+                w.u8((0..8).fold(0x00, |acc, i| (acc << 1) | (screen.get_pixel(x + i, y) as u8)));
+            }
+        }
 
-        *io.audio = self.bus.st.get() > 0;
+        Ok(())
+    }
+
+    pub fn load_state(&mut self, screen: &mut dyn io::Screen, state: &[u8]) -> Result<(), error::Error> {
+        if screen.size() != self.screen_size {
+            return Err(error::Error::InvalidScreenSize(screen.size(), self.screen_size));
+        }
+
+        if self.has_movie() {
+            return Err(error::Error::MovieInProgress);
+        }
+
+        // Everything is read and checked before the machine is touched
+        let mut r = state::Reader::new(state)?;
+
+        let cpu = self.cpu.stage(&mut r)?;
+        let board = self.bus.stage(&mut r)?;
+        let cycles = r.u64()?;
+        let frames = r.u64()?;
+        let pad = r.u16()?;
+        let seed = r.u16()?;
+        let rom_crc = r.u16()?;
+        let freq = f32::from_bits(r.u32()?);
+        let ipf = Some(r.u32()?).filter(|ipf| *ipf != 0);
+        let (width, height) = screen.size();
+        let pixels = r.take(width / 8 * height)?;
+
+        if rom_crc != self.rom_crc {
+            return Err(error::Error::StateRomMismatch(rom_crc, self.rom_crc));
+        }
+
+        if freq != self.freq || ipf != self.ipf {
+            return Err(error::Error::StateConfigMismatch);
+        }
+
+        self.bus.restore(&board)?;
+        self.cpu = cpu;
+        self.cycles = cycles;
+        self.frames = frames;
+        for (key, down) in self.pad.iter_mut().enumerate() {
+            *down = (pad >> key) & 1 != 0;
+        }
+        self.seed = seed;
+
+        // Drawing single pixels on a blank screen sets them
+        screen.clear();
+        for (y, row) in pixels.chunks(width / 8).enumerate() {
+            for (x, byte) in (0..width).step_by(8).zip(row) {
+                for i in (0..8).filter(|i| (byte << i) & 0x80 != 0) {
+                    screen.draw((x + i) as u8, y as u8, 0x80);
+                }
+            }
+        }
 
         Ok(())
     }
 
+    // Covers what both ends of a lockstep session must agree on before exchanging inputs
+    pub fn config_hash(&self) -> u16 {
+        let mut crc = crc16::Crc16::start();
+
+        crc.update_u16(self.rom_crc);
+        crc.update_u16(self.seed);
+        crc.update_u64(self.freq.to_bits() as u64);
+        crc.update(self.bus.rng.get_id());
+
+        crc.finish()
+    }
+
+    pub fn checksum(&self, screen: &dyn io::Screen) -> u16 {
+        let mut crc = crc16::Crc16::start();
+
+        self.cpu.checksum(&mut crc);
+        self.bus.checksum(&mut crc);
+
+        // Pixels are packed as in save states
+        let (width, height) = screen.size();
+        crc.update_u16(width as u16);
+        crc.update_u16(height as u16);
+        for y in 0..height {
+            for x in (0..width).step_by(8) {
+                crc.update((x..(x + 8).min(width)).fold(0x00, |acc, x| (acc << 1) | (screen.get_pixel(x, y) as u8)));
+            }
+        }
+
+        crc.finish()
+    }
+
+    #[cfg(feature = "std")]
+    pub fn record_movie(&mut self) -> Result<(), error::Error> {
+        if self.cycles > 0 || self.frames > 0 {
+            return Err(error::Error::MovieStartedLate);
+        }
+
+        self.movie = Some(movie::Session::Record(movie::Movie::new(
+            self.rom_crc,
+            self.seed,
+            self.freq,
+            self.bus.rng.get_id(),
+            self.cpu.get_wait_mode(),
+        )));
+
+        Ok(())
+    }
+
+    #[cfg(feature = "std")]
+    pub fn play_movie(&mut self, movie: movie::Movie) -> Result<(), error::Error> {
+        if self.cycles > 0 || self.frames > 0 {
+            return Err(error::Error::MovieStartedLate);
+        }
+
+        if movie.get_rom_crc() != self.rom_crc {
+            return Err(error::Error::MovieRomMismatch(movie.get_rom_crc(), self.rom_crc));
+        }
+
+        if movie.get_rng() != self.bus.rng.get_id() {
+            return Err(error::Error::MovieRngMismatch(movie.get_rng(), self.bus.rng.get_id()));
+        }
+
+        // Restore the configuration the movie was recorded with
+        self.freq = movie.get_freq();
+        self.clock_cpu = clock::Clock::new(self.freq);
+        self.ipf = None;
+        self.cpu.set_wait_mode(movie.get_wait_mode());
+        self.seed = movie.get_seed();
+        self.bus.rng.seed(self.seed);
+
+        self.movie = Some(movie::Session::Play(movie));
+
+        Ok(())
+    }
+
+    #[cfg(feature = "std")]
+    pub fn take_movie(&mut self) -> Option<movie::Movie> {
+        self.movie.take().map(|session| match session {
+            movie::Session::Record(movie) | movie::Session::Play(movie) => movie,
+        })
+    }
+
+    #[cfg(feature = "std")]
+    pub fn record_audio(&mut self) {
+        self.timeline = Some(audio::Timeline::new());
+    }
+
+    #[cfg(feature = "std")]
+    pub fn take_audio(&mut self) -> Option<audio::Timeline> {
+        self.timeline.take()
+    }
+
+    pub fn view(&self) -> inspect::View<'_, B> {
+        inspect::View::new(self)
+    }
+
+    // Edits are not recorded, so they are rejected while a movie is in progress
+    pub fn edit(&mut self) -> Result<inspect::Editor<'_, B>, error::Error> {
+        if self.has_movie() {
+            return Err(error::Error::MovieInProgress);
+        }
+
+        Ok(inspect::Editor::new(self))
+    }
+
+    pub fn get_bus(&self) -> &B {
+        &self.bus.mem
+    }
+
+    pub fn get_bus_mut(&mut self) -> &mut B {
+        &mut self.bus.mem
+    }
+
     pub fn get_pad_map(&self) -> &[char] {
         &self.pad_map
     }
@@ -154,4 +721,154 @@ impl Chip8 {
     pub fn get_screen_size(&self) -> (usize, usize) {
         self.screen_size
     }
+
+    fn check_io(&self, io: &io::IO) -> Result<(), error::Error> {
+        if io.screen.size() != self.screen_size {
+            return Err(error::Error::InvalidScreenSize(io.screen.size(), self.screen_size));
+        }
+
+        if io.pad.len() != self.pad_map.len() {
+            return Err(error::Error::InvalidPadSize(io.pad.len(), self.pad_map.len()));
+        }
+
+        Ok(())
+    }
+
+    fn run_until(&mut self, now: Duration, io: &mut io::IO) -> Result<(), error::Error> {
+        // Budgets are in host time, so they cover more emulated cycles when running faster
+        let speed = self.timebase.get_speed() as f32;
+
+        // Movies and frame-locked mode run whole frames, so that input and timers stay in lockstep with the CPU
+        if self.has_movie() || self.ipf.is_some() {
+            let elapsed = self.clock_60htz.tick(now);
+            let mut frames = elapsed;
+
+            if let Some(limiter) = &mut self.limiter {
+                frames = limiter.limit_frames(elapsed, self.freq * speed, TIMER_FREQUENCY * speed);
+
+                if limiter.get_policy() == clock::CatchUp::SlowDown {
+                    self.clock_60htz.rewind(elapsed - frames, now);
+                }
+            }
+
+            for _ in 0..frames {
+                self.run_frame(io)?;
+            }
+        } else {
+            self.latch_keys(io.pad);
+
+            let elapsed = (self.clock_cpu.tick(now), self.clock_60htz.tick(now));
+            let (mut cycles, mut ticks) = elapsed;
+
+            if let Some(limiter) = &mut self.limiter {
+                (cycles, ticks) = limiter.limit(elapsed.0, elapsed.1, self.freq * speed, TIMER_FREQUENCY * speed);
+
+                if limiter.get_policy() == clock::CatchUp::SlowDown {
+                    self.clock_cpu.rewind(elapsed.0 - cycles, now);
+                    self.clock_60htz.rewind(elapsed.1 - ticks, now);
+                }
+            }
+
+            for _ in 0..cycles {
+                self.cpu.cycle(&mut self.bus, io)?;
+                self.cycles += 1;
+            }
+
+            for _ in 0..ticks {
+                self.tick_timers();
+            }
+        }
+
+        Ok(())
+    }
+
+    fn run_frame(&mut self, io: &mut io::IO) -> Result<(), error::Error> {
+        let mut pad = [false; KEY_MAP.len()];
+        pad.copy_from_slice(io.pad);
+
+        #[cfg(feature = "std")]
+        if self.movie.is_some() {
+            let frame = self.frames as usize;
+            let checksum = self.checksum(&*io.screen);
+
+            match &mut self.movie {
+                Some(movie::Session::Record(movie)) => movie.push(&pad, checksum),
+                Some(movie::Session::Play(movie)) => {
+                    movie.verify(frame, checksum)?;
+
+                    pad = movie.get_pad(frame).ok_or(error::Error::MovieFinished(frame))?;
+                }
+                None => {}
+            }
+        }
+
+        // Movies only store pad snapshots, so edges are derived from them alone
+        self.latch_keys(&pad);
+
+        let mut io = io::IO {
+            screen: &mut *io.screen,
+            pad: &pad,
+            audio: &mut *io.audio,
+        };
+
+        // Spread cycles evenly across frames, without accumulating rounding errors
+        let cycles_per_frame = (self.freq as f64) / (TIMER_FREQUENCY as f64);
+        let start = ((self.frames as f64) * cycles_per_frame) as u64;
+        let end = (((self.frames + 1) as f64) * cycles_per_frame) as u64;
+
+        for _ in start..end {
+            self.cpu.cycle(&mut self.bus, &mut io)?;
+            self.cycles += 1;
+        }
+
+        self.tick_timers();
+        self.frames += 1;
+
+        Ok(())
+    }
+
+    fn tick_timers(&mut self) {
+        self.bus.dt.clock();
+        self.bus.st.clock();
+
+        #[cfg(feature = "std")]
+        {
+            let beep = self.is_beeping();
+
+            if let Some(timeline) = &mut self.timeline {
+                timeline.push(beep);
+            }
+        }
+    }
+
+    fn latch_keys(&mut self, pad: &[bool]) {
+        for (key, (prev, down)) in self.pad.iter_mut().zip(pad).enumerate() {
+            if *prev != *down {
+                self.cpu.key_event(if *down {
+                    io::KeyEvent::Press(key)
+                } else {
+                    io::KeyEvent::Release(key)
+                });
+                *prev = *down;
+            }
+        }
+    }
+
+    fn host_now(&self) -> Duration {
+        #[cfg(feature = "std")]
+        return self.time.now();
+        #[cfg(not(feature = "std"))]
+        return self.time;
+    }
+
+    fn has_movie(&self) -> bool {
+        #[cfg(feature = "std")]
+        return self.movie.is_some();
+        #[cfg(not(feature = "std"))]
+        return false;
+    }
+
+    fn is_beeping(&self) -> bool {
+        self.bus.st.get() > 0 || self.cpu.is_beeping()
+    }
 }
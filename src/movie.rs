@@ -0,0 +1,179 @@
+use crate::cpu::WaitMode;
+use crate::error::Error;
+use crate::{pack_keys, unpack_keys, KEY_MAP, MOVIE_CHECKSUM_INTERVAL};
+
+const MOVIE_MAGIC: [u8; 4] = *b"C8MV";
+const MOVIE_VERSION: u8 = 1;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Movie {
+    rom_crc: u16,
+    seed: u16,
+    freq: f32,
+    rng: u8,
+    wait_mode: WaitMode,
+    interval: u32,
+    frames: Vec<u16>,
+    checksums: Vec<u16>,
+}
+
+#[derive(Debug)]
+pub(crate) enum Session {
+    Record(Movie),
+    Play(Movie),
+}
+
+impl Movie {
+    pub fn new(rom_crc: u16, seed: u16, freq: f32, rng: u8, wait_mode: WaitMode) -> Self {
+        Self {
+            rom_crc,
+            seed,
+            freq,
+            rng,
+            wait_mode,
+            interval: MOVIE_CHECKSUM_INTERVAL,
+            frames: Vec::new(),
+            checksums: Vec::new(),
+        }
+    }
+
+    pub fn get_rom_crc(&self) -> u16 {
+        self.rom_crc
+    }
+
+    pub fn get_seed(&self) -> u16 {
+        self.seed
+    }
+
+    pub fn get_freq(&self) -> f32 {
+        self.freq
+    }
+
+    pub fn get_rng(&self) -> u8 {
+        self.rng
+    }
+
+    pub fn get_wait_mode(&self) -> WaitMode {
+        self.wait_mode
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+
+    pub fn push(&mut self, pad: &[bool], checksum: u16) {
+        if self.frames.len().is_multiple_of(self.interval as usize) {
+            self.checksums.push(checksum);
+        }
+
+        self.frames.push(pack_keys(pad));
+    }
+
+    // Returns None past the last recorded frame
+    pub fn get_pad(&self, frame: usize) -> Option<[bool; KEY_MAP.len()]> {
+        self.frames.get(frame).map(|&keys| unpack_keys(keys))
+    }
+
+    pub fn verify(&self, frame: usize, checksum: u16) -> Result<(), Error> {
+        if !frame.is_multiple_of(self.interval as usize) {
+            return Ok(());
+        }
+
+        match self.checksums.get(frame / (self.interval as usize)) {
+            Some(expected) if *expected != checksum => Err(Error::MovieDesync(frame)),
+            _ => Ok(()),
+        }
+    }
+
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(27 + 2 * (self.frames.len() + self.checksums.len()));
+
+        bytes.extend_from_slice(&MOVIE_MAGIC);
+        bytes.push(MOVIE_VERSION);
+        bytes.extend_from_slice(&self.rom_crc.to_le_bytes());
+        bytes.extend_from_slice(&self.seed.to_le_bytes());
+        bytes.extend_from_slice(&self.freq.to_le_bytes());
+        bytes.push(self.rng);
+        bytes.push(self.wait_mode as u8);
+        bytes.extend_from_slice(&self.interval.to_le_bytes());
+
+        for words in [&self.frames, &self.checksums] {
+            bytes.extend_from_slice(&(words.len() as u32).to_le_bytes());
+            words
+                .iter()
+                .for_each(|word| bytes.extend_from_slice(&word.to_le_bytes()));
+        }
+
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let mut reader = Reader { bytes };
+
+        if reader.take(4)? != MOVIE_MAGIC || reader.take(1)? != [MOVIE_VERSION] {
+            return Err(Error::InvalidMovie);
+        }
+
+        let rom_crc = reader.u16()?;
+        let seed = reader.u16()?;
+        let freq = f32::from_le_bytes(reader.u32()?.to_le_bytes());
+        let rng = reader.take(1)?[0];
+        let wait_mode = match reader.take(1)?[0] {
+            0x00 => WaitMode::Press,
+            0x01 => WaitMode::Release,
+            0x02 => WaitMode::Vip,
+            _ => return Err(Error::InvalidMovie),
+        };
+        let interval = reader.u32()?;
+        let frames = reader.words()?;
+        let checksums = reader.words()?;
+
+        if !reader.bytes.is_empty() || interval == 0 || !freq.is_normal() || freq.is_sign_negative() {
+            return Err(Error::InvalidMovie);
+        }
+
+        Ok(Self {
+            rom_crc,
+            seed,
+            freq,
+            rng,
+            wait_mode,
+            interval,
+            frames,
+            checksums,
+        })
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        if len <= self.bytes.len() {
+            let (head, tail) = self.bytes.split_at(len);
+            self.bytes = tail;
+            Ok(head)
+        } else {
+            Err(Error::InvalidMovie)
+        }
+    }
+
+    fn u16(&mut self) -> Result<u16, Error> {
+        self.take(2).map(|b| u16::from_le_bytes([b[0], b[1]]))
+    }
+
+    fn u32(&mut self) -> Result<u32, Error> {
+        self.take(4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    fn words(&mut self) -> Result<Vec<u16>, Error> {
+        let len = self.u32()? as usize;
+        (0..len).map(|_| self.u16()).collect()
+    }
+}
@@ -0,0 +1,272 @@
+use std::collections::VecDeque;
+use std::io::ErrorKind;
+use std::net::{SocketAddr, UdpSocket};
+
+use crate::bus::Bus;
+use crate::error::Error;
+use crate::state::STATE_SIZE;
+use crate::{io, pack_keys, unpack_keys, Chip8};
+
+const NETPLAY_MAGIC: [u8; 4] = *b"C8NP";
+const NETPLAY_INPUT_DELAY: u32 = 2;
+const NETPLAY_MAX_INPUT_DELAY: u32 = 16;
+const NETPLAY_MAX_ROLLBACK: u64 = 16;
+const NETPLAY_CHECKSUM_INTERVAL: u64 = 60;
+const NETPLAY_CHECKSUM_HISTORY: usize = 4;
+
+// Inputs are kept from the oldest frame that may be rolled back, up to the furthest the peer may send
+const NETPLAY_WINDOW: u64 = 128;
+const NETPLAY_SNAPSHOTS: u64 = NETPLAY_MAX_ROLLBACK + 1;
+
+const HEADER_SIZE: usize = 4 + 2 + 8 + 8 + 8 + 2 + 1;
+const PACKET_SIZE: usize = HEADER_SIZE + 2 * NETPLAY_WINDOW as usize;
+
+// Both peers run the same machine on the union of their pads, remote inputs not received yet are predicted
+// to stay unchanged, and frames are emulated again from a snapshot once a prediction turns out wrong
+#[derive(Debug)]
+pub struct Netplay {
+    socket: UdpSocket,
+    peer: SocketAddr,
+    delay: u64,
+    frame: u64,
+    local: Vec<u16>,
+    remote: Vec<u16>,
+    predicted: Vec<u16>,
+    // Remote inputs are known for all frames before this one
+    remote_next: u64,
+    // The peer knows local inputs for all frames before this one
+    remote_ack: u64,
+    snapshots: Vec<[u8; STATE_SIZE]>,
+    checksums: Vec<u16>,
+    next_checksum: u64,
+    confirmed: VecDeque<(u64, u16)>,
+    peer_checksum: Option<(u64, u16)>,
+    rollbacks: u64,
+    // Hash of the local machine configuration, sent with every packet
+    config: u16,
+}
+
+struct Packet<'a> {
+    config: u16,
+    ack: u64,
+    start: u64,
+    checksum: Option<(u64, u16)>,
+    inputs: &'a [u8],
+}
+
+impl Netplay {
+    pub fn new(socket: UdpSocket, peer: SocketAddr, delay: Option<u32>) -> Result<Self, Error> {
+        let delay = delay.unwrap_or(NETPLAY_INPUT_DELAY);
+
+        if delay > NETPLAY_MAX_INPUT_DELAY {
+            return Err(Error::InvalidInputDelay(delay));
+        }
+
+        socket.set_nonblocking(true).map_err(|err| Error::Network(err.kind()))?;
+
+        Ok(Self {
+            socket,
+            peer,
+            delay: delay as u64,
+            frame: 0,
+            local: vec![0x0000; NETPLAY_WINDOW as usize],
+            remote: vec![0x0000; NETPLAY_WINDOW as usize],
+            predicted: vec![0x0000; NETPLAY_WINDOW as usize],
+            remote_next: 0,
+            remote_ack: 0,
+            snapshots: vec![[0x00; STATE_SIZE]; NETPLAY_SNAPSHOTS as usize],
+            checksums: vec![0x0000; NETPLAY_SNAPSHOTS as usize],
+            next_checksum: 0,
+            confirmed: VecDeque::with_capacity(NETPLAY_CHECKSUM_HISTORY),
+            peer_checksum: None,
+            rollbacks: 0,
+            config: 0x0000,
+        })
+    }
+
+    // Frames emulated so far, including predicted ones
+    pub fn get_frame(&self) -> u64 {
+        self.frame
+    }
+
+    pub fn get_delay(&self) -> u32 {
+        self.delay as u32
+    }
+
+    pub fn get_rollbacks(&self) -> u64 {
+        self.rollbacks
+    }
+
+    // The pad of the IO is the local input, returns false while waiting for the peer to catch up
+    pub fn frame<B: Bus>(&mut self, chip8: &mut Chip8<B>, io: &mut io::IO) -> Result<bool, Error> {
+        self.config = chip8.config_hash();
+
+        if let Some(frame) = self.receive()? {
+            chip8.load_state(&mut *io.screen, &self.snapshots[Self::slot(frame)])?;
+
+            for frame in frame..self.frame {
+                self.run(chip8, io, frame)?;
+            }
+
+            self.rollbacks += 1;
+        }
+
+        self.confirm()?;
+
+        let running = self.frame < self.remote_next + NETPLAY_MAX_ROLLBACK;
+
+        if running {
+            self.local[Self::index(self.frame + self.delay)] = pack_keys(io.pad);
+            self.run(chip8, io, self.frame)?;
+            self.frame += 1;
+        }
+
+        self.send()?;
+
+        Ok(running)
+    }
+
+    fn run<B: Bus>(&mut self, chip8: &mut Chip8<B>, io: &mut io::IO, frame: u64) -> Result<(), Error> {
+        let slot = Self::slot(frame);
+        chip8.save_state(&*io.screen, &mut self.snapshots[slot])?;
+        self.checksums[slot] = chip8.checksum(&*io.screen);
+
+        let remote = match (frame < self.remote_next, self.remote_next) {
+            (true, _) => self.remote[Self::index(frame)],
+            (false, 0) => 0x0000,
+            (false, next) => self.remote[Self::index(next - 1)],
+        };
+        self.predicted[Self::index(frame)] = remote;
+
+        let pad = unpack_keys(self.local[Self::index(frame)] | remote);
+
+        chip8.frame(&mut io::IO {
+            screen: &mut *io.screen,
+            pad: &pad,
+            audio: &mut *io.audio,
+        })
+    }
+
+    // Returns the first emulated frame whose remote input was mispredicted
+    fn receive(&mut self) -> Result<Option<u64>, Error> {
+        let mut buf = [0x00; PACKET_SIZE];
+        let mut mispredicted = None;
+
+        loop {
+            let packet = match self.socket.recv_from(&mut buf) {
+                Ok((len, addr)) if addr == self.peer => match Packet::parse(&buf[..len]) {
+                    Some(packet) => packet,
+                    None => continue,
+                },
+                Ok(_) => continue,
+                Err(err) if err.kind() == ErrorKind::WouldBlock => return Ok(mispredicted),
+                // Reported by some systems while the peer is not listening yet
+                Err(err) if matches!(err.kind(), ErrorKind::ConnectionRefused | ErrorKind::ConnectionReset) => {
+                    continue;
+                }
+                Err(err) => return Err(Error::Network(err.kind())),
+            };
+
+            if packet.config != self.config {
+                return Err(Error::NetplayConfigMismatch(self.config, packet.config));
+            }
+
+            self.remote_ack = self.remote_ack.max(packet.ack);
+
+            if packet.checksum.is_some() {
+                self.peer_checksum = packet.checksum;
+            }
+
+            let oldest = self.frame.saturating_sub(NETPLAY_MAX_ROLLBACK);
+
+            for (frame, input) in (packet.start..).zip(packet.inputs.chunks_exact(2)) {
+                // Packets may be reordered, only the next missing input is accepted
+                if frame != self.remote_next || frame >= oldest + NETPLAY_WINDOW {
+                    continue;
+                }
+
+                let keys = u16::from_le_bytes([input[0], input[1]]);
+                self.remote[Self::index(frame)] = keys;
+                self.remote_next += 1;
+
+                if frame < self.frame && self.predicted[Self::index(frame)] != keys && mispredicted.is_none() {
+                    mispredicted = Some(frame);
+                }
+            }
+        }
+    }
+
+    // Local inputs are resent until acknowledged, so lost packets need no special handling
+    fn send(&mut self) -> Result<(), Error> {
+        let next = self.frame + self.delay;
+        let start = self.remote_ack.max(next.saturating_sub(NETPLAY_WINDOW));
+        let (checksum_frame, checksum) = self.confirmed.back().copied().unwrap_or((u64::MAX, 0x0000));
+
+        let mut packet = Vec::with_capacity(PACKET_SIZE);
+        packet.extend_from_slice(&NETPLAY_MAGIC);
+        packet.extend_from_slice(&self.config.to_le_bytes());
+        packet.extend_from_slice(&self.remote_next.to_le_bytes());
+        packet.extend_from_slice(&start.to_le_bytes());
+        packet.extend_from_slice(&checksum_frame.to_le_bytes());
+        packet.extend_from_slice(&checksum.to_le_bytes());
+        packet.push((next - start) as u8);
+        (start..next).for_each(|frame| packet.extend_from_slice(&self.local[Self::index(frame)].to_le_bytes()));
+
+        match self.socket.send_to(&packet, self.peer) {
+            Ok(_) => Ok(()),
+            Err(err) if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::ConnectionRefused) => Ok(()),
+            Err(err) => Err(Error::Network(err.kind())),
+        }
+    }
+
+    // Snapshots are final once all inputs before them are known, their checksums are then compared
+    fn confirm(&mut self) -> Result<(), Error> {
+        while self.next_checksum < self.frame && self.next_checksum <= self.remote_next {
+            if self.confirmed.len() == NETPLAY_CHECKSUM_HISTORY {
+                self.confirmed.pop_front();
+            }
+
+            let checksum = self.checksums[Self::slot(self.next_checksum)];
+            self.confirmed.push_back((self.next_checksum, checksum));
+            self.next_checksum += NETPLAY_CHECKSUM_INTERVAL;
+        }
+
+        match self.peer_checksum {
+            Some((frame, expected)) if self.confirmed.iter().any(|&(f, c)| f == frame && c != expected) => {
+                Err(Error::NetplayDesync(frame))
+            }
+            _ => Ok(()),
+        }
+    }
+
+    fn index(frame: u64) -> usize {
+        (frame % NETPLAY_WINDOW) as usize
+    }
+
+    fn slot(frame: u64) -> usize {
+        (frame % NETPLAY_SNAPSHOTS) as usize
+    }
+}
+
+impl<'a> Packet<'a> {
+    fn parse(bytes: &'a [u8]) -> Option<Self> {
+        if bytes.len() < HEADER_SIZE || bytes[..4] != NETPLAY_MAGIC {
+            return None;
+        }
+
+        let u64_at = |i: usize| u64::from_le_bytes(bytes[i..i + 8].try_into().unwrap());
+        let count = bytes[HEADER_SIZE - 1] as usize;
+        let inputs = bytes.get(HEADER_SIZE..HEADER_SIZE + 2 * count)?;
+
+        Some(Self {
+            config: u16::from_le_bytes([bytes[4], bytes[5]]),
+            ack: u64_at(6),
+            start: u64_at(14),
+            checksum: match u64_at(22) {
+                u64::MAX => None,
+                frame => Some((frame, u16::from_le_bytes([bytes[30], bytes[31]]))),
+            },
+            inputs,
+        })
+    }
+}
@@ -0,0 +1,169 @@
+use std::collections::HashMap;
+use std::io;
+
+use crate::bus::MEMORY_SIZE;
+
+#[derive(Debug, Clone)]
+pub struct Profiler {
+    counts: Vec<u64>,
+    stack: Vec<u16>,
+    folded: HashMap<Vec<u16>, u64>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Subroutine {
+    pub addr: Option<u16>,
+    pub exclusive: u64,
+    pub inclusive: u64,
+}
+
+impl Profiler {
+    pub fn new() -> Self {
+        Self {
+            counts: vec![0; MEMORY_SIZE],
+            stack: Vec::new(),
+            folded: HashMap::new(),
+        }
+    }
+
+    pub fn exec(&mut self, pc: u16) {
+        if let Some(count) = self.counts.get_mut(pc as usize) {
+            *count += 1;
+        }
+
+        match self.folded.get_mut(self.stack.as_slice()) {
+            Some(count) => *count += 1,
+            None => {
+                self.folded.insert(self.stack.clone(), 1);
+            }
+        }
+    }
+
+    pub fn call(&mut self, addr: u16) {
+        self.stack.push(addr);
+    }
+
+    pub fn ret(&mut self) {
+        // Returns past the point where profiling started are attributed to the top level
+        self.stack.pop();
+    }
+
+    pub fn get_count(&self, addr: u16) -> u64 {
+        self.counts.get(addr as usize).copied().unwrap_or_default()
+    }
+
+    pub fn get_total(&self) -> u64 {
+        self.counts.iter().sum()
+    }
+
+    // Top level code outside of any subroutine has no address
+    pub fn get_subroutines(&self) -> Vec<Subroutine> {
+        let mut subroutines: HashMap<Option<u16>, Subroutine> = HashMap::new();
+
+        for (stack, count) in &self.folded {
+            let frames: Vec<_> = [None].into_iter().chain(stack.iter().copied().map(Some)).collect();
+
+            for (depth, addr) in frames.iter().copied().enumerate() {
+                let entry = subroutines.entry(addr).or_insert(Subroutine {
+                    addr,
+                    exclusive: 0,
+                    inclusive: 0,
+                });
+
+                // Recursive calls are only accounted once in inclusive time
+                if !frames[..depth].contains(&addr) {
+                    entry.inclusive += count;
+                }
+
+                if depth + 1 == frames.len() {
+                    entry.exclusive += count;
+                }
+            }
+        }
+
+        let mut subroutines: Vec<_> = subroutines.into_values().collect();
+        subroutines.sort_by_key(|sub| (std::cmp::Reverse(sub.inclusive), sub.addr));
+        subroutines
+    }
+
+    pub fn write_report<W: io::Write>(&self, mut w: W) -> io::Result<()> {
+        let total = self.get_total().max(1) as f64;
+
+        writeln!(w, "# Subroutines")?;
+        writeln!(
+            w,
+            "{:>8} {:>12} {:>7} {:>12} {:>7}",
+            "addr", "inclusive", "%", "exclusive", "%"
+        )?;
+
+        for sub in self.get_subroutines() {
+            writeln!(
+                w,
+                "{:>8} {:>12} {:>6.2}% {:>12} {:>6.2}%",
+                frame_name(sub.addr),
+                sub.inclusive,
+                100.0 * (sub.inclusive as f64) / total,
+                sub.exclusive,
+                100.0 * (sub.exclusive as f64) / total
+            )?;
+        }
+
+        writeln!(w)?;
+        writeln!(w, "# Instructions")?;
+        writeln!(w, "{:>8} {:>12} {:>7}", "addr", "count", "%")?;
+
+        let mut addrs: Vec<_> = (0..(self.counts.len() as u16))
+            .filter(|addr| self.get_count(*addr) > 0)
+            .collect();
+        addrs.sort_by_key(|addr| (std::cmp::Reverse(self.get_count(*addr)), *addr));
+
+        for addr in addrs {
+            let count = self.get_count(addr);
+            writeln!(
+                w,
+                "{:>8} {:>12} {:>6.2}%",
+                format!("0x{:04x}", addr),
+                count,
+                100.0 * (count as f64) / total
+            )?;
+        }
+
+        w.flush()
+    }
+
+    // One line per call stack, in the format consumed by flamegraph.pl and inferno
+    pub fn write_folded<W: io::Write>(&self, mut w: W) -> io::Result<()> {
+        let mut stacks: Vec<_> = self
+            .folded
+            .iter()
+            .map(|(stack, count)| {
+                let frames: Vec<_> = [None]
+                    .into_iter()
+                    .chain(stack.iter().copied().map(Some))
+                    .map(frame_name)
+                    .collect();
+                (frames.join(";"), count)
+            })
+            .collect();
+        stacks.sort();
+
+        for (stack, count) in stacks {
+            writeln!(w, "{} {}", stack, count)?;
+        }
+
+        w.flush()
+    }
+}
+
+impl Default for Profiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn frame_name(addr: Option<u16>) -> String {
+    match addr {
+        Some(addr) => format!("0x{:04x}", addr),
+        None => String::from("main"),
+    }
+}
@@ -0,0 +1,205 @@
+use core::fmt;
+
+pub trait RandomSource: fmt::Debug {
+    fn seed(&mut self, seed: u16);
+    fn next_byte(&mut self) -> u8;
+
+    // Stored in movies, so that replays with another generator are rejected. Ids up to 0x7f are taken by this crate
+    fn get_id(&self) -> u8;
+
+    // Whole generator state, for inspection and checksums
+    fn get_state(&self) -> u64;
+    fn set_state(&mut self, state: u64);
+}
+
+// 16-bit Fibonacci LFSR, the historical generator of this emulator
+#[derive(Debug, Default, Clone)]
+pub struct Lfsr {
+    state: u16,
+}
+
+// PCG32 (XSH RR), for fuzzing and anything else needing good statistical quality
+#[cfg(feature = "std")]
+#[derive(Debug, Default, Clone)]
+pub struct Pcg {
+    state: u64,
+}
+
+// Modeled after the CXKK routine of the COSMAC VIP interpreter, which mixes a counter with bytes of its own code.
+// The interpreter is not shipped with this crate, so the output only matches the VIP with a dump of the original
+#[cfg(feature = "std")]
+#[derive(Debug, Clone)]
+pub struct Vip {
+    page: [u8; 0x100],
+    state: u16,
+}
+
+// Replays a fixed sequence of bytes, looping at the end
+#[cfg(feature = "std")]
+#[derive(Debug, Clone)]
+pub struct Scripted {
+    bytes: Vec<u8>,
+    pos: usize,
+}
+
+impl Lfsr {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    fn next(&mut self) {
+        // P(X) = X^16 + X^15 + X^13 + X^4 + 1
+        let bit = (self.state ^ (self.state >> 1) ^ (self.state >> 3) ^ (self.state >> 12)) & 0x1;
+        self.state = (self.state >> 1) | (bit << 15);
+    }
+}
+
+impl RandomSource for Lfsr {
+    fn seed(&mut self, seed: u16) {
+        self.state = seed;
+
+        // Filter out first 16 bits, to not directly output the seed
+        for _ in 0..16 {
+            self.next();
+        }
+    }
+
+    fn next_byte(&mut self) -> u8 {
+        (0..8).fold(0x00, |acc, _| {
+            let bit = (self.state & 0x0001) as u8;
+            self.next();
+            (acc << 1) | bit
+        })
+    }
+
+    fn get_id(&self) -> u8 {
+        0x00
+    }
+
+    fn get_state(&self) -> u64 {
+        self.state as u64
+    }
+
+    fn set_state(&mut self, state: u64) {
+        self.state = state as u16;
+    }
+}
+
+#[cfg(feature = "std")]
+impl Pcg {
+    const MULTIPLIER: u64 = 6364136223846793005;
+    const INCREMENT: u64 = 1442695040888963407;
+
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        let old = self.state;
+        self.state = old.wrapping_mul(Self::MULTIPLIER).wrapping_add(Self::INCREMENT);
+
+        let xorshifted = (((old >> 18) ^ old) >> 27) as u32;
+        xorshifted.rotate_right((old >> 59) as u32)
+    }
+}
+
+#[cfg(feature = "std")]
+impl RandomSource for Pcg {
+    fn seed(&mut self, seed: u16) {
+        self.state = 0;
+        self.next_u32();
+        self.state = self.state.wrapping_add(seed as u64);
+        self.next_u32();
+    }
+
+    fn next_byte(&mut self) -> u8 {
+        (self.next_u32() >> 24) as u8
+    }
+
+    fn get_id(&self) -> u8 {
+        0x01
+    }
+
+    fn get_state(&self) -> u64 {
+        self.state
+    }
+
+    fn set_state(&mut self, state: u64) {
+        self.state = state;
+    }
+}
+
+#[cfg(feature = "std")]
+impl Vip {
+    // Takes the 512 bytes of the interpreter, the routine only reads from its second page
+    pub fn new(interpreter: &[u8; 0x200]) -> Self {
+        let mut page = [0x00; 0x100];
+        page.copy_from_slice(&interpreter[0x100..]);
+
+        Self { page, state: 0x0000 }
+    }
+}
+
+#[cfg(feature = "std")]
+impl RandomSource for Vip {
+    // The seed stands for the value of R9 when the ROM starts
+    fn seed(&mut self, seed: u16) {
+        self.state = seed;
+    }
+
+    // R9 is incremented, its low byte selects a code byte that is added to its high byte, which then gets the
+    // result of adding that sum to itself shifted right through the carry
+    fn next_byte(&mut self) -> u8 {
+        let [hi, lo] = self.state.wrapping_add(1).to_be_bytes();
+        let (sum, carry) = self.page[lo as usize].overflowing_add(hi);
+        let hi = ((sum >> 1) | ((carry as u8) << 7)).wrapping_add(sum);
+
+        self.state = u16::from_be_bytes([hi, lo]);
+        hi
+    }
+
+    fn get_id(&self) -> u8 {
+        0x03
+    }
+
+    fn get_state(&self) -> u64 {
+        self.state as u64
+    }
+
+    fn set_state(&mut self, state: u64) {
+        self.state = state as u16;
+    }
+}
+
+#[cfg(feature = "std")]
+impl Scripted {
+    pub fn new(bytes: Vec<u8>) -> Self {
+        Self { bytes, pos: 0 }
+    }
+}
+
+#[cfg(feature = "std")]
+impl RandomSource for Scripted {
+    // Restarts the sequence, the seed itself is ignored
+    fn seed(&mut self, _seed: u16) {
+        self.pos = 0;
+    }
+
+    fn next_byte(&mut self) -> u8 {
+        let byte = self.bytes.get(self.pos).copied().unwrap_or_default();
+        self.pos = (self.pos + 1) % self.bytes.len().max(1);
+        byte
+    }
+
+    fn get_id(&self) -> u8 {
+        0x02
+    }
+
+    fn get_state(&self) -> u64 {
+        self.pos as u64
+    }
+
+    fn set_state(&mut self, state: u64) {
+        self.pos = (state as usize) % self.bytes.len().max(1);
+    }
+}
@@ -0,0 +1,72 @@
+use std::io;
+
+use crate::bus::MEMORY_SIZE;
+
+// Writes made through the editor have no program counter
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SmcEvent {
+    pub pc: Option<u16>,
+    pub addr: u16,
+    pub old: u16,
+    pub new: u16,
+}
+
+#[derive(Debug, Clone)]
+pub struct SmcDetector {
+    executed: Vec<bool>,
+    events: Vec<SmcEvent>,
+}
+
+impl SmcDetector {
+    pub fn new() -> Self {
+        Self {
+            executed: vec![false; MEMORY_SIZE],
+            events: Vec::new(),
+        }
+    }
+
+    pub fn exec(&mut self, pc: u16) {
+        if let Some(executed) = self.executed.get_mut(pc as usize) {
+            *executed = true;
+        }
+    }
+
+    // Start addresses of executed instructions covering a byte
+    pub fn get_instructions(&self, addr: u16) -> impl Iterator<Item = u16> + '_ {
+        [addr.checked_sub(1), Some(addr)]
+            .into_iter()
+            .flatten()
+            .filter(|start| self.executed.get(*start as usize).copied().unwrap_or_default())
+    }
+
+    pub fn push(&mut self, event: SmcEvent) {
+        self.events.push(event);
+    }
+
+    pub fn get_events(&self) -> &[SmcEvent] {
+        &self.events
+    }
+
+    pub fn write_report<W: io::Write>(&self, mut w: W) -> io::Result<()> {
+        for event in &self.events {
+            let source = match event.pc {
+                Some(pc) => format!("0x{:04x}", pc),
+                None => String::from("editor"),
+            };
+
+            writeln!(
+                w,
+                "{}: instruction at 0x{:04x} modified from {:04x} to {:04x}",
+                source, event.addr, event.old, event.new
+            )?;
+        }
+
+        w.flush()
+    }
+}
+
+impl Default for SmcDetector {
+    fn default() -> Self {
+        Self::new()
+    }
+}
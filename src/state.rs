@@ -0,0 +1,103 @@
+use crate::error::Error;
+use crate::{MEMORY_SIZE, SCREEN_SIZE};
+
+const STATE_MAGIC: [u8; 4] = *b"C8ST";
+const STATE_VERSION: u8 = 1;
+
+const HEADER_SIZE: usize = 5;
+const CPU_SIZE: usize = 61;
+const BOARD_SIZE: usize = MEMORY_SIZE + 10;
+const MACHINE_SIZE: usize = 30;
+const SCREEN_BYTES: usize = SCREEN_SIZE.0 * SCREEN_SIZE.1 / 8;
+
+// Save states have a fixed size, so hosts can provide the buffer without allocating
+pub const STATE_SIZE: usize = HEADER_SIZE + CPU_SIZE + BOARD_SIZE + MACHINE_SIZE + SCREEN_BYTES;
+
+pub(crate) struct Writer<'a> {
+    bytes: &'a mut [u8],
+}
+
+pub(crate) struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Writer<'a> {
+    pub fn new(bytes: &'a mut [u8]) -> Result<Self, Error> {
+        if bytes.len() != STATE_SIZE {
+            return Err(Error::InvalidState);
+        }
+
+        let mut writer = Self { bytes };
+        writer.put(&STATE_MAGIC);
+        writer.u8(STATE_VERSION);
+
+        Ok(writer)
+    }
+
+    // Sizes are checked upfront, running out of space is a bug in the layout
+    pub fn put(&mut self, data: &[u8]) {
+        let (head, tail) = core::mem::take(&mut self.bytes).split_at_mut(data.len());
+        head.copy_from_slice(data);
+        self.bytes = tail;
+    }
+
+    pub fn u8(&mut self, value: u8) {
+        self.put(&[value]);
+    }
+
+    pub fn u16(&mut self, value: u16) {
+        self.put(&value.to_le_bytes());
+    }
+
+    pub fn u32(&mut self, value: u32) {
+        self.put(&value.to_le_bytes());
+    }
+
+    pub fn u64(&mut self, value: u64) {
+        self.put(&value.to_le_bytes());
+    }
+}
+
+impl<'a> Reader<'a> {
+    pub fn new(bytes: &'a [u8]) -> Result<Self, Error> {
+        if bytes.len() != STATE_SIZE {
+            return Err(Error::InvalidState);
+        }
+
+        let mut reader = Self { bytes };
+
+        if reader.take(4)? != STATE_MAGIC || reader.u8()? != STATE_VERSION {
+            return Err(Error::InvalidState);
+        }
+
+        Ok(reader)
+    }
+
+    pub fn take(&mut self, len: usize) -> Result<&'a [u8], Error> {
+        if len <= self.bytes.len() {
+            let (head, tail) = self.bytes.split_at(len);
+            self.bytes = tail;
+            Ok(head)
+        } else {
+            Err(Error::InvalidState)
+        }
+    }
+
+    pub fn u8(&mut self) -> Result<u8, Error> {
+        self.take(1).map(|b| b[0])
+    }
+
+    pub fn u16(&mut self) -> Result<u16, Error> {
+        self.take(2).map(|b| u16::from_le_bytes([b[0], b[1]]))
+    }
+
+    pub fn u32(&mut self) -> Result<u32, Error> {
+        self.take(4).map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    pub fn u64(&mut self) -> Result<u64, Error> {
+        let mut bytes = [0x00; 8];
+        bytes.copy_from_slice(self.take(8)?);
+        Ok(u64::from_le_bytes(bytes))
+    }
+}
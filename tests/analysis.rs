@@ -0,0 +1,141 @@
+use chip8::{Analysis, BasicBlock, BlockExit, Routine};
+
+// Skip over a jump to a computed jump, else call a subroutine and loop, with data in between
+const ROM: [u8; 22] = [
+    0x60, 0x00, 0x30, 0x01, 0x12, 0x0a, 0x22, 0x10, 0x12, 0x08, 0xb2, 0x0e, 0xff, 0xff, 0x00, 0xe0, 0x61, 0x01, 0x00,
+    0xee, 0xab, 0xcd,
+];
+
+fn block(start: u16, end: u16, exit: BlockExit, successors: &[u16]) -> BasicBlock {
+    BasicBlock {
+        start,
+        end,
+        exit,
+        successors: successors.to_vec(),
+    }
+}
+
+#[test]
+fn blocks_and_edges() {
+    let analysis = Analysis::new(&ROM);
+
+    assert_eq!(
+        analysis.get_blocks().cloned().collect::<Vec<_>>(),
+        [
+            block(0x0200, 0x0204, BlockExit::Skip, &[0x0204, 0x0206]),
+            block(0x0204, 0x0206, BlockExit::Jump(0x020a), &[0x020a]),
+            block(0x0206, 0x0208, BlockExit::Call(0x0210), &[0x0208]),
+            block(0x0208, 0x020a, BlockExit::Jump(0x0208), &[0x0208]),
+            block(0x020a, 0x020c, BlockExit::ComputedJump(0x020e), &[]),
+            block(0x0210, 0x0214, BlockExit::Return, &[]),
+        ]
+    );
+    assert_eq!(analysis.get_computed_jumps(), [0x020a]);
+}
+
+#[test]
+fn routines() {
+    let analysis = Analysis::new(&ROM);
+
+    // Calls are not followed into the callee
+    assert_eq!(
+        analysis.get_routines().cloned().collect::<Vec<_>>(),
+        [
+            Routine {
+                entry: 0x0200,
+                blocks: vec![0x0200, 0x0204, 0x0206, 0x0208, 0x020a],
+                calls: vec![0x0210],
+            },
+            Routine {
+                entry: 0x0210,
+                blocks: vec![0x0210],
+                calls: vec![],
+            },
+        ]
+    );
+}
+
+#[test]
+fn unreachable() {
+    // Targets of computed jumps are not known statically
+    assert_eq!(
+        Analysis::new(&ROM).get_unreachable(),
+        [(0x020c, 0x0210), (0x0214, 0x0216)]
+    );
+}
+
+#[test]
+fn invalid_and_truncated() {
+    let analysis = Analysis::new(&[0x60, 0x00, 0x30, 0x00, 0x50, 0x01]);
+
+    assert_eq!(
+        analysis.get_blocks().cloned().collect::<Vec<_>>(),
+        [
+            block(0x0200, 0x0204, BlockExit::Skip, &[0x0204, 0x0206]),
+            block(0x0204, 0x0206, BlockExit::Invalid([0x5, 0x0, 0x0, 0x1]), &[]),
+            block(0x0206, 0x0206, BlockExit::EndOfRom, &[]),
+        ]
+    );
+    assert!(analysis.get_unreachable().is_empty());
+}
+
+#[test]
+fn dot() {
+    let mut dot = Vec::new();
+    Analysis::new(&ROM).write_dot(&mut dot).unwrap();
+
+    assert_eq!(
+        String::from_utf8(dot).unwrap(),
+        r#"digraph rom {
+    node [shape=box, fontname=monospace];
+    subgraph cluster_0200 {
+        label="main";
+        "0200_0200" [label="0x0200-0x0204\nskip"];
+        "0200_0204" [label="0x0204-0x0206\njump 0x020a"];
+        "0200_0206" [label="0x0206-0x0208\ncall 0x0210"];
+        "0200_0208" [label="0x0208-0x020a\njump 0x0208"];
+        "0200_020a" [label="0x020a-0x020c\ncomputed jump 0x020e+V0", color=red];
+        "0200_0200" -> "0200_0204";
+        "0200_0200" -> "0200_0206";
+        "0200_0204" -> "0200_020a";
+        "0200_0206" -> "0200_0208";
+        "0200_0208" -> "0200_0208";
+    }
+    subgraph cluster_0210 {
+        label="sub_0210";
+        "0210_0210" [label="0x0210-0x0214\nreturn"];
+    }
+    "0200_0206" -> "0210_0210" [style=dashed];
+}
+"#
+    );
+}
+
+#[test]
+fn json() {
+    let mut json = Vec::new();
+    Analysis::new(&ROM).write_json(&mut json).unwrap();
+
+    assert_eq!(
+        String::from_utf8(json).unwrap(),
+        r#"{
+  "start": 512,
+  "end": 534,
+  "blocks": [
+    { "start": 512, "end": 516, "exit": "skip", "successors": [516, 518] },
+    { "start": 516, "end": 518, "exit": "jump 0x020a", "successors": [522] },
+    { "start": 518, "end": 520, "exit": "call 0x0210", "successors": [520] },
+    { "start": 520, "end": 522, "exit": "jump 0x0208", "successors": [520] },
+    { "start": 522, "end": 524, "exit": "computed jump 0x020e+V0", "successors": [] },
+    { "start": 528, "end": 532, "exit": "return", "successors": [] }
+  ],
+  "routines": [
+    { "entry": 512, "blocks": [512, 516, 518, 520, 522], "calls": [528] },
+    { "entry": 528, "blocks": [528], "calls": [] }
+  ],
+  "computed_jumps": [522],
+  "unreachable": [{ "start": 524, "end": 528 }, { "start": 532, "end": 534 }]
+}
+"#
+    );
+}
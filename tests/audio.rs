@@ -0,0 +1,93 @@
+use chip8::{AudioRenderer, Chip8, Error, Framebuffer, Timeline, IO};
+
+// Starts the buzzer, extends it while sounding, cuts it early with ST = 0, then lets a new beep expire
+const ROM: [u8; 16] = [
+    0x60, 0x04, 0x61, 0x08, 0x62, 0x00, 0xf0, 0x18, 0xf1, 0x18, 0xf2, 0x18, 0xf0, 0x18, 0x12, 0x0e,
+];
+
+fn record(frames: usize) -> Timeline {
+    let mut chip8 = Chip8::new(None);
+    chip8.load_rom(&ROM, None).unwrap();
+    chip8.set_ipf(1).unwrap();
+    chip8.record_audio();
+
+    let mut screen = Framebuffer::default();
+    let mut audio = false;
+
+    for _ in 0..frames {
+        chip8
+            .frame(&mut IO {
+                screen: &mut screen,
+                pad: &[false; 0x10],
+                audio: &mut audio,
+            })
+            .unwrap();
+    }
+
+    chip8.take_audio().unwrap()
+}
+
+#[test]
+fn timeline_follows_st_writes() {
+    let timeline = record(12);
+
+    // One instruction per frame, the timer ticks right after it
+    assert_eq!(
+        timeline.get_transitions(),
+        [(3, true), (5, false), (6, true), (9, false)]
+    );
+    assert_eq!(timeline.get_ticks(), 12);
+    assert_eq!(timeline.get_duration().as_millis(), 200);
+}
+
+#[test]
+fn wav_export() {
+    let timeline = record(12);
+    let renderer = AudioRenderer::new(Some(600)).unwrap();
+
+    // 10 samples per tick at 600 Hz
+    let samples = renderer.render(&timeline);
+    assert_eq!(samples.len(), 120);
+    for (i, sample) in samples.iter().enumerate() {
+        let beeping = (30..50).contains(&i) || (60..90).contains(&i);
+        assert_eq!(*sample != 0, beeping, "sample {}", i);
+    }
+
+    let mut wav = Vec::new();
+    renderer.write_wav(&timeline, &mut wav).unwrap();
+
+    let u16_at = |at: usize| u16::from_le_bytes(wav[at..at + 2].try_into().unwrap());
+    let u32_at = |at: usize| u32::from_le_bytes(wav[at..at + 4].try_into().unwrap());
+
+    assert_eq!(wav.len(), 44 + 240);
+    assert_eq!(&wav[0..4], b"RIFF");
+    assert_eq!(u32_at(4), 36 + 240);
+    assert_eq!(&wav[8..16], b"WAVEfmt ");
+    assert_eq!(u32_at(16), 16);
+    assert_eq!(u16_at(20), 1);
+    assert_eq!(u16_at(22), 1);
+    assert_eq!(u32_at(24), 600);
+    assert_eq!(u32_at(28), 1200);
+    assert_eq!(u16_at(32), 2);
+    assert_eq!(u16_at(34), 16);
+    assert_eq!(&wav[36..40], b"data");
+    assert_eq!(u32_at(40), 240);
+
+    let data: Vec<i16> = wav[44..]
+        .chunks_exact(2)
+        .map(|sample| i16::from_le_bytes([sample[0], sample[1]]))
+        .collect();
+    assert_eq!(data, samples);
+}
+
+#[test]
+fn silent_timeline() {
+    let mut timeline = Timeline::new();
+    (0..6).for_each(|_| timeline.push(false));
+
+    let renderer = AudioRenderer::new(None).unwrap();
+    assert!(timeline.get_transitions().is_empty());
+    assert_eq!(renderer.render(&timeline), vec![0; 4410]);
+
+    assert!(matches!(AudioRenderer::new(Some(0)), Err(Error::InvalidSampleRate(0))));
+}
@@ -0,0 +1,120 @@
+use chip8::{Batch, Chip8, Error, Framebuffer, IO, STATE_SIZE};
+
+// Counts loops with key 1 held in 0x300, and draws the random byte from the seed
+const ROM: [u8; 22] = [
+    0xc1, 0xff, 0xa3, 0x00, 0x62, 0x01, 0xe2, 0x9e, 0x12, 0x06, 0x70, 0x01, 0xa3, 0x00, 0xf0, 0x55, 0xf1, 0x29, 0xd0,
+    0x05, 0x12, 0x06,
+];
+
+// Same machine run alone on the calling thread
+fn single(seed: u16, keys: u16, frames: u32) -> Vec<u8> {
+    let mut chip8 = Chip8::new(None);
+    let mut screen = Framebuffer::default();
+    chip8.load_rom(&ROM, Some(seed)).unwrap();
+
+    let pad: Vec<bool> = (0..0x10).map(|i| (keys >> i) & 0x1 != 0).collect();
+
+    for _ in 0..frames {
+        chip8
+            .frame(&mut IO {
+                screen: &mut screen,
+                pad: &pad,
+                audio: &mut false,
+            })
+            .unwrap();
+    }
+
+    let mut state = vec![0x00; STATE_SIZE];
+    chip8.save_state(&screen, &mut state).unwrap();
+    state
+}
+
+#[test]
+fn matches_single_machines() {
+    let mut batch = Batch::new(&ROM, 10, None).unwrap();
+    batch.set_threads(Some(3)).unwrap();
+
+    for i in 0..batch.len() {
+        batch.reset(i, Some(i as u16)).unwrap();
+        batch.set_keys(i, if i % 2 == 0 { 0x0002 } else { 0x0000 }).unwrap();
+    }
+
+    batch.step(30);
+    batch.step(30);
+
+    let states = batch.to_states();
+    for (i, state) in states.chunks_exact(STATE_SIZE).enumerate() {
+        let keys = if i % 2 == 0 { 0x0002 } else { 0x0000 };
+        assert_eq!(state, single(i as u16, keys, 60), "instance {}", i);
+    }
+
+    let (width, height) = batch.get_screen_size();
+    let pixels = batch.to_framebuffers();
+    assert_eq!(pixels.len(), 10 * width * height);
+    assert_eq!(&pixels[..width * height], batch.get_screen(0).unwrap().to_bools());
+    assert!((0..batch.len()).all(|i| batch.get_error(i).is_none()));
+}
+
+#[test]
+fn errors_only_halt_their_instance() {
+    // Counts loops in V0, and runs into an undefined instruction once key 0xf is held
+    let rom = [0x62, 0x0f, 0xe2, 0xa1, 0xff, 0xff, 0x70, 0x01, 0x12, 0x02];
+    let mut batch = Batch::new(&rom, 6, None).unwrap();
+    batch.set_threads(Some(4)).unwrap();
+    batch.set_keys(1, 0x8000).unwrap();
+    batch.set_keys(4, 0x8000).unwrap();
+
+    batch.step(1);
+
+    for i in 0..batch.len() {
+        let halted = matches!(batch.get_error(i), Some(Error::UndefinedInstruction(_)));
+        assert_eq!(halted, i == 1 || i == 4, "instance {}", i);
+    }
+
+    // Halted instances stay put while the others keep running
+    let v0 = |batch: &Batch, i: usize| batch.get_chip8(i).unwrap().view().get_v()[0];
+    let before: Vec<u8> = (0..batch.len()).map(|i| v0(&batch, i)).collect();
+    let cycles = batch.get_chip8(1).unwrap().view().get_cycles();
+
+    batch.step(1);
+
+    assert_eq!(batch.get_chip8(1).unwrap().view().get_cycles(), cycles);
+    assert!(v0(&batch, 0) > before[0] && v0(&batch, 5) > before[5]);
+
+    // Until they are reset
+    batch.reset(1, None).unwrap();
+    assert!(batch.get_error(1).is_none());
+
+    batch.step(1);
+    assert!(batch.get_error(1).is_none() && batch.get_error(4).is_some());
+    assert!(v0(&batch, 1) > 0);
+
+    // Fewer instances than threads, then a single thread
+    batch.set_threads(Some(16)).unwrap();
+    batch.step(1);
+    batch.set_threads(Some(1)).unwrap();
+    batch.step(1);
+    assert_eq!(batch.len(), 6);
+    assert!(batch.get_error(1).is_none() && batch.get_error(4).is_some());
+}
+
+#[test]
+fn invalid_arguments() {
+    let mut batch = Batch::new(&ROM, 2, None).unwrap();
+
+    assert!(matches!(batch.set_threads(Some(0)), Err(Error::InvalidThreadCount(0))));
+    assert!(matches!(batch.set_keys(2, 0x0000), Err(Error::InstanceOutOfRange(2))));
+    assert!(matches!(batch.reset(5, None), Err(Error::InstanceOutOfRange(5))));
+    assert!(matches!(
+        batch.write_states(&mut [0x00; 10]),
+        Err(Error::InvalidBufferSize(10, _))
+    ));
+}
+
+#[test]
+fn machines_are_send() {
+    fn assert_send<T: Send>() {}
+
+    assert_send::<Chip8>();
+    assert_send::<Batch>();
+}
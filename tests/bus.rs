@@ -0,0 +1,128 @@
+use std::sync::{Arc, Mutex};
+
+use chip8::{Bus, Chip8, Error, Framebuffer, MappedBus, Ram, Screen, IO};
+
+// Registers answering reads with their offset, and logging writes
+#[derive(Default)]
+struct Device {
+    writes: Arc<Mutex<Vec<(u16, u8)>>>,
+}
+
+impl Bus for Device {
+    fn read(&mut self, addr: u16) -> Result<u8, Error> {
+        self.peek(addr)
+    }
+
+    fn write(&mut self, addr: u16, byte: u8) -> Result<(), Error> {
+        self.writes.lock().unwrap().push((addr, byte));
+        Ok(())
+    }
+
+    fn peek(&self, addr: u16) -> Result<u8, Error> {
+        Ok(0x80 | addr as u8)
+    }
+}
+
+// Read-only font with every glyph a filled block
+struct Font;
+
+impl Bus for Font {
+    fn read(&mut self, addr: u16) -> Result<u8, Error> {
+        self.peek(addr)
+    }
+
+    fn write(&mut self, _addr: u16, _byte: u8) -> Result<(), Error> {
+        Ok(())
+    }
+
+    fn peek(&self, _addr: u16) -> Result<u8, Error> {
+        Ok(0xff)
+    }
+}
+
+#[test]
+fn routing() {
+    let device = Device::default();
+    let writes = device.writes.clone();
+
+    let mut bus = MappedBus::new(Ram::default());
+    bus.map(0x0f00..0x0f10, device).unwrap();
+
+    // Devices see relative addresses
+    assert_eq!(bus.read(0x0f03).unwrap(), 0x83);
+    assert_eq!(bus.peek(0x0f0f).unwrap(), 0x8f);
+    bus.write(0x0f04, 0x42).unwrap();
+    assert_eq!(*writes.lock().unwrap(), [(0x0004, 0x42)]);
+    assert_eq!(bus.get_base().peek(0x0f04).unwrap(), 0x00);
+
+    // Everything else goes to the base memory
+    bus.write(0x0f10, 0x24).unwrap();
+    assert_eq!(bus.read(0x0f10).unwrap(), 0x24);
+    assert_eq!(bus.get_base().peek(0x0f10).unwrap(), 0x24);
+    assert_eq!(writes.lock().unwrap().len(), 1);
+}
+
+#[test]
+fn unmapped_addresses() {
+    let mut bus = MappedBus::new(Ram::default());
+    bus.map(0x0f00..0x0f10, Device::default()).unwrap();
+
+    assert!(matches!(bus.read(0x1000), Err(Error::RamOutOfRange(0x1000))));
+    assert!(matches!(bus.write(0xffff, 0x00), Err(Error::RamOutOfRange(0xffff))));
+    assert!(matches!(bus.peek(0x1234), Err(Error::RamOutOfRange(0x1234))));
+}
+
+#[test]
+fn overlaps_are_rejected() {
+    let mut bus = MappedBus::new(Ram::default());
+    bus.map(0x0f00..0x0f10, Device::default()).unwrap();
+
+    assert!(matches!(
+        bus.map(0x0f08..0x0f18, Device::default()),
+        Err(Error::InvalidMapping(0x0f08, 0x0f18))
+    ));
+    assert!(matches!(
+        bus.map(0x0e00..0x1000, Device::default()),
+        Err(Error::InvalidMapping(0x0e00, 0x1000))
+    ));
+    assert!(matches!(
+        bus.map(0x0e00..0x0e00, Device::default()),
+        Err(Error::InvalidMapping(0x0e00, 0x0e00))
+    ));
+
+    // Adjacent ranges do not overlap
+    bus.map(0x0f10..0x0f20, Device::default()).unwrap();
+    bus.map(0x0ef0..0x0f00, Device::default()).unwrap();
+    assert_eq!(bus.peek(0x0f10).unwrap(), 0x80);
+    assert_eq!(bus.peek(0x0eff).unwrap(), 0x8f);
+}
+
+#[test]
+fn device_over_font() {
+    let mut bus = MappedBus::new(Ram::default());
+    bus.map(0x0000..0x0050, Font).unwrap();
+
+    // Draws the glyph of digit 0 in the top left corner
+    let mut chip8 = Chip8::with_bus(None, bus);
+    chip8
+        .load_rom(&[0x60, 0x00, 0xf0, 0x29, 0xd0, 0x05, 0x12, 0x06], None)
+        .unwrap();
+    chip8.set_ipf(3).unwrap();
+
+    let mut screen = Framebuffer::default();
+    chip8
+        .frame(&mut IO {
+            screen: &mut screen,
+            pad: &[false; 0x10],
+            audio: &mut false,
+        })
+        .unwrap();
+
+    // The built-in font was written to the device and ignored
+    assert_eq!(chip8.get_bus().get_base().peek(0x0000).unwrap(), 0x00);
+    for y in 0..8 {
+        for x in 0..10 {
+            assert_eq!(screen.get_pixel(x, y), x < 8 && y < 5, "pixel ({}, {})", x, y);
+        }
+    }
+}
@@ -0,0 +1,349 @@
+use std::time::Duration;
+
+use chip8::{CatchUp, Chip8, Error, Framebuffer, ManualTime, ScaledTime, IO};
+
+// V0 = 0xff, DT = V0, then loop forever
+const ROM: [u8; 6] = [0x60, 0xff, 0xf0, 0x15, 0x12, 0x04];
+
+fn clock(chip8: &mut Chip8) {
+    let mut screen = Framebuffer::default();
+    let mut audio = false;
+
+    chip8
+        .clock(&mut IO {
+            screen: &mut screen,
+            pad: &[false; 0x10],
+            audio: &mut audio,
+        })
+        .unwrap();
+}
+
+fn load(time: ManualTime) -> Chip8 {
+    let mut chip8 = Chip8::new(None);
+    chip8.load_rom(&ROM, None).unwrap();
+    chip8.set_time_source(time);
+    chip8
+}
+
+#[test]
+fn manual_time_stops_without_advance() {
+    let time = ManualTime::new();
+    let mut chip8 = load(time);
+
+    clock(&mut chip8);
+    let cycles = chip8.view().get_cycles();
+
+    clock(&mut chip8);
+    clock(&mut chip8);
+
+    assert_eq!(chip8.view().get_cycles(), cycles);
+}
+
+#[test]
+fn sixty_timer_ticks_per_second() {
+    let time = ManualTime::new();
+    let mut chip8 = load(time.clone());
+
+    clock(&mut chip8);
+    time.advance(Duration::from_millis(10));
+    clock(&mut chip8);
+
+    let dt = chip8.view().get_dt();
+
+    // Uneven steps must not accumulate rounding errors
+    for _ in 0..300 {
+        time.advance(Duration::from_nanos(3_333_333));
+        clock(&mut chip8);
+    }
+    time.advance(Duration::from_nanos(100));
+    clock(&mut chip8);
+
+    assert_eq!(dt - chip8.view().get_dt(), 60);
+}
+
+#[test]
+fn cycles_follow_cpu_frequency() {
+    let time = ManualTime::new();
+    let mut chip8 = load(time.clone());
+
+    clock(&mut chip8);
+    time.advance(Duration::from_secs(2));
+    clock(&mut chip8);
+
+    assert_eq!(chip8.view().get_cycles(), 1001);
+}
+
+#[test]
+fn speed_keeps_timers_per_emulated_second() {
+    let time = ManualTime::new();
+    let mut chip8 = load(time.clone());
+    chip8.set_speed(2.0).unwrap();
+
+    clock(&mut chip8);
+    time.advance(Duration::from_millis(5));
+    clock(&mut chip8);
+
+    let dt = chip8.view().get_dt();
+    let cycles = chip8.view().get_cycles();
+
+    time.advance(Duration::from_millis(500));
+    clock(&mut chip8);
+
+    assert_eq!(dt - chip8.view().get_dt(), 60);
+    assert_eq!(chip8.view().get_cycles() - cycles, 500);
+}
+
+#[test]
+fn invalid_speed() {
+    let mut chip8 = load(ManualTime::new());
+
+    for speed in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+        assert!(matches!(chip8.set_speed(speed), Err(Error::InvalidTimeScale(_))));
+    }
+
+    assert_eq!(chip8.get_speed(), 1.0);
+}
+
+#[test]
+fn scaled_time_keeps_timers_per_emulated_second() {
+    let time = ManualTime::new();
+    let mut chip8 = load(time.clone());
+    chip8.set_time_source(ScaledTime::new(time.clone(), 2.0).unwrap());
+
+    clock(&mut chip8);
+    time.advance(Duration::from_millis(5));
+    clock(&mut chip8);
+
+    let dt = chip8.view().get_dt();
+    let cycles = chip8.view().get_cycles();
+
+    time.advance(Duration::from_millis(500));
+    clock(&mut chip8);
+
+    assert_eq!(dt - chip8.view().get_dt(), 60);
+    assert_eq!(chip8.view().get_cycles() - cycles, 500);
+}
+
+#[test]
+fn scale_and_speed_compound() {
+    let time = ManualTime::new();
+    let mut chip8 = load(time.clone());
+    chip8.set_time_source(ScaledTime::new(time.clone(), 2.0).unwrap());
+    chip8.set_speed(0.5).unwrap();
+
+    clock(&mut chip8);
+    time.advance(Duration::from_millis(5));
+    clock(&mut chip8);
+
+    let cycles = chip8.view().get_cycles();
+
+    time.advance(Duration::from_secs(1));
+    clock(&mut chip8);
+
+    assert_eq!(chip8.view().get_cycles() - cycles, 500);
+}
+
+#[test]
+fn invalid_time_scale() {
+    for scale in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+        assert!(matches!(
+            ScaledTime::new(ManualTime::new(), scale),
+            Err(Error::InvalidTimeScale(_))
+        ));
+    }
+
+    assert_eq!(ScaledTime::new(ManualTime::new(), 2.0).unwrap().get_scale(), 2.0);
+}
+
+#[test]
+fn drop_limits_cycles_after_stall() {
+    let time = ManualTime::new();
+    let mut chip8 = load(time.clone());
+    chip8.set_catch_up(CatchUp::Drop, Some(Duration::from_millis(100)));
+
+    clock(&mut chip8);
+    time.advance(Duration::from_secs(10));
+    clock(&mut chip8);
+
+    assert_eq!(chip8.view().get_cycles(), 51);
+    assert_eq!(chip8.get_dropped_cycles(), 4950);
+    assert!(!chip8.is_behind());
+
+    // Timer ticks are skipped along with the cycles
+    assert_eq!(chip8.view().get_dt(), 0xff - 6);
+}
+
+#[test]
+fn slow_down_limits_the_whole_machine_after_stall() {
+    let time = ManualTime::new();
+    let mut chip8 = load(time.clone());
+    chip8.set_catch_up(CatchUp::SlowDown, Some(Duration::from_millis(100)));
+
+    clock(&mut chip8);
+    time.advance(Duration::from_secs(10));
+    clock(&mut chip8);
+
+    assert_eq!(chip8.view().get_cycles(), 51);
+    assert_eq!(chip8.view().get_dt(), 0xff - 6);
+    assert_eq!(chip8.get_dropped_cycles(), 0);
+    assert!(!chip8.is_behind());
+
+    // The missed time is not caught up afterwards
+    for _ in 0..10 {
+        time.advance(Duration::from_millis(100));
+        clock(&mut chip8);
+    }
+
+    assert_eq!(chip8.view().get_cycles(), 551);
+    assert_eq!(chip8.view().get_dt(), 0xff - 66);
+}
+
+#[test]
+fn skip_frames_catches_up_over_several_calls() {
+    let time = ManualTime::new();
+    let mut chip8 = load(time.clone());
+    chip8.set_catch_up(CatchUp::SkipFrames, Some(Duration::from_millis(100)));
+
+    clock(&mut chip8);
+    time.advance(Duration::from_secs(1));
+    clock(&mut chip8);
+
+    assert!(chip8.is_behind());
+
+    for _ in 0..9 {
+        clock(&mut chip8);
+    }
+
+    assert!(!chip8.is_behind());
+    assert_eq!(chip8.view().get_cycles(), 501);
+    assert_eq!(chip8.get_dropped_cycles(), 0);
+}
+
+#[test]
+fn skip_frames_backlog_is_capped() {
+    let time = ManualTime::new();
+    let mut chip8 = load(time.clone());
+    chip8.set_catch_up(CatchUp::SkipFrames, Some(Duration::from_millis(100)));
+
+    clock(&mut chip8);
+    time.advance(Duration::from_secs(10));
+
+    // Ten budgets are kept, the rest of the stall is dropped
+    for _ in 0..10 {
+        clock(&mut chip8);
+    }
+
+    assert!(!chip8.is_behind());
+    assert_eq!(chip8.view().get_cycles(), 501);
+    assert_eq!(chip8.get_dropped_cycles(), 4500);
+}
+
+#[test]
+fn frame_locked_drop_and_slow_down() {
+    for (policy, dropped) in [(CatchUp::Drop, 5940), (CatchUp::SlowDown, 0)] {
+        let time = ManualTime::new();
+        let mut chip8 = load(time.clone());
+        chip8.set_ipf(10).unwrap();
+        chip8.set_catch_up(policy, Some(Duration::from_millis(100)));
+
+        clock(&mut chip8);
+        time.advance(Duration::from_secs(10));
+        clock(&mut chip8);
+
+        // Whole frames are limited, so timers stay in lockstep with the CPU
+        assert_eq!(chip8.view().get_cycles(), 70, "{:?}", policy);
+        assert_eq!(chip8.view().get_dt(), 0xff - 7, "{:?}", policy);
+        assert_eq!(chip8.get_dropped_cycles(), dropped, "{:?}", policy);
+    }
+}
+
+#[test]
+fn pause_stops_emulation() {
+    let time = ManualTime::new();
+    let mut chip8 = load(time.clone());
+
+    clock(&mut chip8);
+    chip8.pause();
+    time.advance(Duration::from_secs(1));
+    clock(&mut chip8);
+
+    assert_eq!(chip8.view().get_cycles(), 1);
+
+    chip8.resume();
+    time.advance(Duration::from_secs(1));
+    clock(&mut chip8);
+
+    assert_eq!(chip8.view().get_cycles(), 501);
+}
+
+#[test]
+fn step_frame_runs_one_timer_tick() {
+    let time = ManualTime::new();
+    let mut chip8 = load(time.clone());
+
+    clock(&mut chip8);
+    time.advance(Duration::from_millis(10));
+    clock(&mut chip8);
+
+    let dt = chip8.view().get_dt();
+    let mut screen = Framebuffer::default();
+    let mut audio = false;
+
+    for _ in 0..60 {
+        chip8
+            .step_frame(&mut IO {
+                screen: &mut screen,
+                pad: &[false; 0x10],
+                audio: &mut audio,
+            })
+            .unwrap();
+    }
+
+    assert!(chip8.is_paused());
+    assert_eq!(dt - chip8.view().get_dt(), 60);
+    assert_eq!(chip8.view().get_cycles(), 501);
+}
+
+#[test]
+fn speed_and_frequency_change_while_running() {
+    let time = ManualTime::new();
+    let mut chip8 = load(time.clone());
+
+    clock(&mut chip8);
+    chip8.set_speed(4.0).unwrap();
+    time.advance(Duration::from_millis(250));
+    clock(&mut chip8);
+
+    assert_eq!(chip8.view().get_cycles(), 501);
+
+    chip8.set_freq(1000.0).unwrap();
+    time.advance(Duration::from_millis(250));
+    clock(&mut chip8);
+
+    assert_eq!(chip8.view().get_cycles(), 1501);
+    assert!(chip8.set_speed(0.0).is_err());
+    assert!(chip8.set_freq(-1.0).is_err());
+}
+
+#[test]
+fn frame_locked_runs_fixed_instructions_per_frame() {
+    let time = ManualTime::new();
+    let mut chip8 = load(time.clone());
+    chip8.set_ipf(15).unwrap();
+
+    clock(&mut chip8);
+    assert_eq!(chip8.view().get_cycles(), 15);
+
+    time.advance(Duration::from_millis(10));
+    clock(&mut chip8);
+    assert_eq!(chip8.view().get_cycles(), 15);
+
+    time.advance(Duration::from_secs(1));
+    clock(&mut chip8);
+    assert_eq!(chip8.view().get_cycles(), 61 * 15);
+    assert_eq!(chip8.get_ipf(), Some(15));
+    assert!(chip8.set_ipf(0).is_err());
+
+    chip8.set_freq(500.0).unwrap();
+    assert_eq!(chip8.get_ipf(), None);
+}
@@ -0,0 +1,92 @@
+use chip8::{Access, Chip8, Framebuffer, Region, IO};
+
+// Draws a sprite, then stores and loads over its own loop instruction
+const ROM: [u8; 15] = [
+    0x60, 0x12, 0xa2, 0x0e, 0xd0, 0x11, 0xa2, 0x0c, 0xf0, 0x55, 0xf0, 0x65, 0x12, 0x0c, 0xf0,
+];
+
+fn run() -> (Chip8, chip8::Coverage) {
+    let mut chip8 = Chip8::new(None);
+    chip8.load_rom(&ROM, None).unwrap();
+    chip8.set_ipf(10).unwrap();
+    chip8.record_coverage();
+
+    let mut screen = Framebuffer::default();
+    chip8
+        .frame(&mut IO {
+            screen: &mut screen,
+            pad: &[false; 0x10],
+            audio: &mut false,
+        })
+        .unwrap();
+
+    let coverage = chip8.take_coverage().unwrap();
+    (chip8, coverage)
+}
+
+#[test]
+fn accesses() {
+    let (_, coverage) = run();
+
+    assert!(coverage.has(0x0200, Access::Exec) && coverage.has(0x0201, Access::Exec));
+    assert!(coverage.has(0x020e, Access::Sprite));
+    assert!(coverage.has(0x020c, Access::Write) && coverage.has(0x020c, Access::Read));
+    assert!(!coverage.has(0x020d, Access::Write));
+    assert!(!coverage.has(0x0000, Access::Read));
+
+    assert_eq!(coverage.get_region(0x0204), Region::Code);
+    assert_eq!(coverage.get_region(0x020c), Region::Mixed);
+    assert_eq!(coverage.get_region(0x020e), Region::Data);
+    assert_eq!(coverage.get_region(0x020f), Region::Unused);
+}
+
+#[test]
+fn regions_are_merged() {
+    let (_, coverage) = run();
+
+    assert_eq!(
+        coverage.get_regions(),
+        [
+            (0x0000, 0x0200, Region::Unused),
+            (0x0200, 0x020c, Region::Code),
+            (0x020c, 0x020d, Region::Mixed),
+            (0x020d, 0x020e, Region::Code),
+            (0x020e, 0x020f, Region::Data),
+            (0x020f, 0x1000, Region::Unused),
+        ]
+    );
+}
+
+#[test]
+fn hexdump() {
+    let (chip8, coverage) = run();
+
+    let mut memory = [0x00; 0x1000];
+    chip8.view().read(0x0000, &mut memory).unwrap();
+
+    let mut dump = Vec::new();
+    coverage.write_hexdump(&memory, &mut dump).unwrap();
+
+    // Untouched lines are skipped
+    assert_eq!(
+        String::from_utf8(dump).unwrap(),
+        "# C: code, S: sprite, R: read, W: written, D: several data accesses, !: code and data\n\
+         0200: 60 12 a2 0e d0 11 a2 0c f0 55 f0 65 12 0c f0 00  |CCCCCCCCCCCC!CS.|\n"
+    );
+}
+
+#[test]
+fn json() {
+    let (_, coverage) = run();
+
+    let mut json = Vec::new();
+    coverage.write_json(&mut json).unwrap();
+    let json = String::from_utf8(json).unwrap();
+
+    assert!(json.starts_with(
+        "{\n  \"regions\": [\n    { \"start\": 0, \"end\": 512, \"kind\": \"unused\" },\n    \
+         { \"start\": 512, \"end\": 524, \"kind\": \"code\" },\n"
+    ));
+    assert!(json.contains("    { \"addr\": 524, \"access\": [\"exec\", \"read\", \"write\"] },\n"));
+    assert!(json.ends_with("    { \"addr\": 526, \"access\": [\"sprite\"] }\n  ]\n}\n"));
+}
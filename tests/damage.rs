@@ -0,0 +1,120 @@
+use chip8::{Framebuffer, Rect, Screen};
+
+struct Recorder {
+    pixels: Vec<bool>,
+    damage: Vec<Rect>,
+}
+
+impl Screen for Recorder {
+    fn get_pixel(&self, x: usize, y: usize) -> bool {
+        self.pixels[y * 64 + x]
+    }
+
+    fn set_pixel(&mut self, x: usize, y: usize, on: bool) {
+        self.pixels[y * 64 + x] = on;
+    }
+
+    fn damage(&mut self, rect: Rect) {
+        self.damage.push(rect);
+    }
+
+    fn get_width(&self) -> usize {
+        64
+    }
+
+    fn get_height(&self) -> usize {
+        32
+    }
+}
+
+#[test]
+fn union() {
+    let rect = Rect::new(10, 3, 8, 1).union(&Rect::new(2, 20, 4, 2));
+    assert_eq!(rect, Rect::new(2, 3, 16, 19));
+    assert_eq!(rect.rows(), 3..22);
+
+    assert_eq!(rect.union(&Rect::new(4, 4, 1, 1)), rect);
+}
+
+#[test]
+fn draw() {
+    let mut screen = Framebuffer::default();
+    assert_eq!(screen.take_damage(), None);
+
+    screen.draw(10, 3, 0x81);
+    assert_eq!(screen.take_damage(), Some(Rect::new(10, 3, 8, 1)));
+
+    // Positions are wrapped before computing the area
+    screen.draw(64 + 10, 32 + 3, 0x81);
+    assert_eq!(screen.take_damage(), Some(Rect::new(10, 3, 8, 1)));
+
+    // Empty sprite lines change nothing
+    screen.draw(10, 3, 0x00);
+    assert_eq!(screen.take_damage(), None);
+}
+
+#[test]
+fn draw_clipped_at_edge() {
+    let mut screen = Framebuffer::default();
+
+    // Sprites reaching the right edge exactly stay within their 8 columns
+    screen.draw(56, 31, 0xff);
+    assert_eq!(screen.take_damage(), Some(Rect::new(56, 31, 8, 1)));
+
+    // Past the edge the sprite wraps, so the whole line is damaged
+    screen.draw(60, 31, 0xff);
+    assert_eq!(screen.take_damage(), Some(Rect::new(0, 31, 64, 1)));
+}
+
+#[test]
+fn draw_union() {
+    let mut screen = Framebuffer::default();
+
+    screen.draw(10, 3, 0xff);
+    screen.draw(20, 7, 0xff);
+    screen.draw(4, 5, 0xff);
+    assert!(screen.is_changed());
+    assert_eq!(screen.take_damage(), Some(Rect::new(4, 3, 24, 5)));
+    assert!(!screen.is_changed());
+
+    screen.draw(10, 3, 0xff);
+    screen.draw(62, 10, 0xff);
+    assert_eq!(screen.take_damage(), Some(Rect::new(0, 3, 64, 8)));
+}
+
+#[test]
+fn clear() {
+    let mut screen = Framebuffer::default();
+
+    // Clearing a blank screen changes nothing
+    screen.clear();
+    assert_eq!(screen.take_damage(), None);
+
+    screen.draw(10, 3, 0xff);
+    screen.take_damage();
+    screen.clear();
+    assert_eq!(screen.take_damage(), Some(Rect::new(0, 0, 64, 32)));
+
+    screen.resize(128, 64).unwrap();
+    assert_eq!(screen.take_damage(), Some(Rect::new(0, 0, 128, 64)));
+}
+
+#[test]
+fn slice_screen() {
+    let mut screen = Recorder {
+        pixels: vec![false; 64 * 32],
+        damage: Vec::new(),
+    };
+
+    screen.clear();
+    screen.draw(10, 3, 0x00);
+    assert!(screen.damage.is_empty());
+
+    screen.draw(10, 3, 0xff);
+    screen.draw(60, 31, 0xff);
+    screen.clear();
+    assert_eq!(
+        screen.damage,
+        [Rect::new(10, 3, 8, 1), Rect::new(0, 31, 64, 1), Rect::new(0, 0, 64, 32),]
+    );
+}
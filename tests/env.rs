@@ -0,0 +1,112 @@
+use chip8::{Condition, Env, Error, Reward};
+
+// Counts from 1 to 5 in 0x300, then sets 0x302 and loops forever
+const COUNTER: [u8; 18] = [
+    0xa3, 0x00, 0x70, 0x01, 0xf0, 0x55, 0x30, 0x05, 0x12, 0x02, 0x61, 0x01, 0xa3, 0x01, 0xf1, 0x55, 0x12, 0x10,
+];
+
+// Counts loops with key 1 held in 0x300
+const KEYS: [u8; 16] = [
+    0xa3, 0x00, 0x62, 0x01, 0xe2, 0x9e, 0x12, 0x04, 0x70, 0x01, 0xa3, 0x00, 0xf0, 0x55, 0x12, 0x04,
+];
+
+fn counter() -> Env {
+    let mut env = Env::new(&COUNTER, None).unwrap();
+    env.set_ipf(1).unwrap();
+    env.add_reward(Reward::Delta(0x0300, 0.5)).unwrap();
+    env.add_done(Condition::Equal(0x0302, 0x01)).unwrap();
+    env
+}
+
+fn episode(env: &mut Env) -> (f32, u32) {
+    let mut total = 0.0;
+    let mut steps = 0;
+
+    loop {
+        let (_, reward, done) = env.step(0x0000).unwrap();
+        total += reward;
+        steps += 1;
+
+        if done {
+            return (total, steps);
+        }
+    }
+}
+
+#[test]
+fn rewards_and_done() {
+    let mut env = counter();
+
+    let (total, steps) = episode(&mut env);
+    assert_eq!(total, 2.5);
+
+    // Episodes are repeatable after a reset, frame skip only changes the step count
+    env.reset(Some(0x1234)).unwrap();
+    env.set_frame_skip(4).unwrap();
+
+    let (skipped_total, skipped_steps) = episode(&mut env);
+    assert_eq!(skipped_total, 2.5);
+    assert_eq!(skipped_steps, steps.div_ceil(4));
+}
+
+#[test]
+fn sticky_actions() {
+    let mut env = Env::new(&KEYS, None).unwrap();
+    env.add_reward(Reward::Delta(0x0300, 1.0)).unwrap();
+
+    // Always repeating the initial action means no key is ever pressed
+    env.set_sticky_actions(1.0).unwrap();
+    let rewards: f32 = (0..10).map(|_| env.step(0x0002).unwrap().1).sum();
+    assert_eq!(rewards, 0.0);
+
+    env.reset(None).unwrap();
+    env.set_sticky_actions(0.0).unwrap();
+    let rewards: f32 = (0..10).map(|_| env.step(0x0002).unwrap().1).sum();
+    assert!(rewards > 0.0);
+}
+
+#[test]
+fn sticky_actions_follow_the_reset_seed() {
+    let mut env = Env::new(&KEYS, None).unwrap();
+    env.add_reward(Reward::Delta(0x0300, 1.0)).unwrap();
+    env.set_sticky_actions(0.5).unwrap();
+
+    let mut rewards = |seed| {
+        env.reset(Some(seed)).unwrap();
+        (0..64)
+            .map(|step| env.step(0x0002 * (step % 2)).unwrap().1)
+            .collect::<Vec<_>>()
+    };
+
+    let first = rewards(0x1234);
+    assert_eq!(rewards(0x1234), first);
+    assert_ne!(rewards(0x4321), first);
+}
+
+#[test]
+fn parallel_instances() {
+    let results: Vec<(f32, u32)> = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..4)
+            .map(|_| {
+                let mut env = counter();
+                scope.spawn(move || episode(&mut env))
+            })
+            .collect();
+
+        handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+    });
+
+    assert!(results.iter().all(|result| *result == results[0]));
+}
+
+#[test]
+fn invalid_settings() {
+    let mut env = counter();
+
+    assert!(matches!(env.set_frame_skip(0), Err(Error::InvalidFrameSkip(0))));
+    assert!(matches!(env.set_sticky_actions(1.5), Err(Error::InvalidProbability(_))));
+    assert!(matches!(
+        env.add_done(Condition::Equal(0x1000, 0x00)),
+        Err(Error::RamOutOfRange(0x1000))
+    ));
+}
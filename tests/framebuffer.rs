@@ -0,0 +1,122 @@
+use chip8::{Error, Framebuffer, Rect, Screen};
+
+#[test]
+fn draw_xor_and_collision() {
+    let mut screen = Framebuffer::default();
+
+    assert!(!screen.draw(4, 2, 0b1100_0000));
+    assert_eq!(screen.get_row(2), 0b11 << 4);
+
+    // Overlapping pixels are erased and reported, the others are set
+    assert!(screen.draw(5, 2, 0b1100_0000));
+    assert_eq!(screen.get_row(2), 0b101 << 4);
+    assert!(screen.get_pixel(4, 2) && !screen.get_pixel(5, 2) && screen.get_pixel(6, 2));
+
+    assert!(!screen.draw(0, 2, 0x00));
+    assert!(!screen.draw(12, 2, 0xff));
+}
+
+#[test]
+fn draw_wraps_around_edges() {
+    let mut screen = Framebuffer::default();
+
+    // Right edge, the sprite continues at the start of the same row
+    screen.draw(60, 5, 0xff);
+    assert_eq!(screen.get_row(5), (0xf << 60) | 0xf);
+
+    // Positions past the screen wrap before drawing
+    screen.draw(64 + 1, 32 + 3, 0x80);
+    assert_eq!(screen.get_row(3), 0b10);
+}
+
+#[test]
+fn narrow_screens_are_masked() {
+    let mut screen = Framebuffer::new(60, 40).unwrap();
+
+    // Bits past the width are never set, and wrapped pixels land at the row start
+    screen.draw(58, 39, 0xff);
+    assert_eq!(screen.get_row(39), (0b11 << 58) | 0b11_1111);
+    assert_eq!(screen.get_rows().len(), 40);
+
+    screen.draw(0, 40, 0x80);
+    assert_eq!(screen.get_row(0), 0b1);
+
+    assert!(matches!(
+        Framebuffer::new(7, 32),
+        Err(Error::UnsupportedScreenSize((7, 32)))
+    ));
+    assert!(matches!(
+        Framebuffer::new(129, 32),
+        Err(Error::UnsupportedScreenSize((129, 32)))
+    ));
+    assert!(matches!(
+        Framebuffer::new(64, 65),
+        Err(Error::UnsupportedScreenSize((64, 65)))
+    ));
+
+    // The widest screen keeps every bit of the row word
+    let mut screen = Framebuffer::new(128, 64).unwrap();
+    screen.draw(124, 63, 0xff);
+    assert_eq!(screen.get_row(63), (0xf << 124) | 0xf);
+}
+
+#[test]
+fn clear() {
+    let mut screen = Framebuffer::default();
+    screen.draw(10, 10, 0xaa);
+    screen.draw(10, 31, 0xaa);
+
+    screen.clear();
+    assert!(screen.get_rows().iter().all(|row| *row == 0));
+    assert!(!screen.draw(10, 10, 0xaa));
+}
+
+#[test]
+fn set_pixel() {
+    let mut screen = Framebuffer::default();
+    screen.take_damage();
+
+    screen.set_pixel(63, 31, true);
+    assert!(screen.get_pixel(63, 31));
+    assert_eq!(screen.get_row(31), 1 << 63);
+    assert_eq!(screen.take_damage(), Some(Rect::new(63, 31, 1, 1)));
+
+    // Unchanged pixels are not damaged
+    screen.set_pixel(63, 31, true);
+    assert_eq!(screen.take_damage(), None);
+
+    screen.set_pixel(63, 31, false);
+    assert_eq!(screen.get_row(31), 0);
+}
+
+#[test]
+fn conversions() {
+    let mut screen = Framebuffer::new(8, 2).unwrap();
+    screen.draw(0, 0, 0b1000_0001);
+    screen.draw(3, 1, 0b1000_0000);
+
+    assert_eq!(screen.to_text(), "#......#\n...#....\n");
+
+    let bools = screen.to_bools();
+    assert_eq!(bools.len(), 16);
+    let lit: Vec<usize> = (0..bools.len()).filter(|i| bools[*i]).collect();
+    assert_eq!(lit, [0, 7, 11]);
+
+    // Writers leave anything past the screen untouched
+    let mut out = [true; 17];
+    screen.write_bools(&mut out);
+    assert_eq!(out[..16], bools[..]);
+    assert!(out[16]);
+
+    let (fg, bg) = ([0xff, 0xff, 0xff, 0xff], [0x00, 0x00, 0x00, 0xff]);
+    let rgba = screen.to_rgba(fg, bg);
+    assert_eq!(rgba.len(), 16 * 4);
+    for (i, px) in rgba.chunks_exact(4).enumerate() {
+        assert_eq!(px, if bools[i] { fg } else { bg });
+    }
+
+    let mut out = [0x55; 16 * 4 + 1];
+    screen.write_rgba(&mut out, fg, bg);
+    assert_eq!(out[..16 * 4], rgba[..]);
+    assert_eq!(out[16 * 4], 0x55);
+}
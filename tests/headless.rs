@@ -0,0 +1,172 @@
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, ChildStdout, Command, Stdio};
+
+use serde_json::{json, Value};
+
+// Waits for a key, draws its glyph in the top left corner and sounds the buzzer for 5 ticks
+const ROM: [u8; 12] = [0xf0, 0x0a, 0xf0, 0x29, 0xd1, 0x25, 0x60, 0x05, 0xf0, 0x18, 0x12, 0x0a];
+
+struct Headless {
+    child: Child,
+    stdin: ChildStdin,
+    stdout: BufReader<ChildStdout>,
+}
+
+impl Headless {
+    fn spawn() -> Self {
+        let mut child = Command::new(env!("CARGO_BIN_EXE_chip8-headless"))
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+            .unwrap();
+
+        Self {
+            stdin: child.stdin.take().unwrap(),
+            stdout: BufReader::new(child.stdout.take().unwrap()),
+            child,
+        }
+    }
+
+    fn send(&mut self, line: &str) -> Value {
+        writeln!(self.stdin, "{}", line).unwrap();
+        self.stdin.flush().unwrap();
+
+        let mut response = String::new();
+        self.stdout.read_line(&mut response).unwrap();
+        serde_json::from_str(&response).unwrap()
+    }
+
+    fn request(&mut self, request: Value) -> Value {
+        let response = self.send(&request.to_string());
+        assert_eq!(response["ok"], json!(true), "{}", response);
+        response
+    }
+
+    fn error(&mut self, line: &str) -> String {
+        let response = self.send(line);
+        assert_eq!(response["ok"], json!(false), "{}", response);
+        response["error"].as_str().unwrap().into()
+    }
+}
+
+impl Drop for Headless {
+    fn drop(&mut self) {
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
+
+#[test]
+fn session() {
+    let mut headless = Headless::spawn();
+
+    let response = headless.request(json!({ "id": 1, "cmd": "load", "rom": ROM, "seed": 0x1234 }));
+    assert_eq!(response["id"], json!(1));
+    assert_eq!(response["size"], json!(ROM.len()));
+
+    // The machine waits for a key
+    headless.request(json!({ "cmd": "step", "count": 4 }));
+    assert_eq!(headless.request(json!({ "cmd": "registers" }))["pc"], json!(0x0200));
+
+    headless.request(json!({ "cmd": "press", "key": 5 }));
+    headless.request(json!({ "cmd": "release", "key": 5 }));
+    let response = headless.request(json!({ "cmd": "step", "count": 5 }));
+    assert_eq!(response["beep"], json!(true));
+
+    let registers = headless.request(json!({ "cmd": "registers" }));
+    assert_eq!(registers["pc"], json!(0x020a));
+    assert_eq!(registers["v"][0], json!(5));
+    assert_eq!(registers["st"], json!(5));
+    assert_eq!(registers["cycles"], json!(4 + 5));
+
+    // Glyph 5 is 0xf0, 0x80, 0xf0, 0x10, 0xf0
+    let screenshot = headless.request(json!({ "cmd": "screenshot" }));
+    assert_eq!(screenshot["width"], json!(64));
+    assert_eq!(screenshot["height"], json!(32));
+    let rows = screenshot["rows"].as_array().unwrap();
+    assert_eq!(rows.len(), 32);
+    assert_eq!(rows[1].as_str().unwrap(), format!("#{}", ".".repeat(63)));
+    assert_eq!(rows[3].as_str().unwrap(), format!("...#{}", ".".repeat(60)));
+
+    let response = headless.request(json!({ "cmd": "run_frames", "count": 10 }));
+    assert_eq!(response["beep"], json!(false));
+
+    headless.request(json!({ "cmd": "poke", "addr": 0x0300, "bytes": [1, 2, 3] }));
+    let response = headless.request(json!({ "cmd": "peek", "addr": 0x02ff, "len": 5 }));
+    assert_eq!(response["bytes"], json!([0, 1, 2, 3, 0]));
+    let response = headless.request(json!({ "cmd": "peek", "addr": 0x0200 }));
+    assert_eq!(response["bytes"], json!([0xf0]));
+
+    headless.request(json!({ "cmd": "quit" }));
+    assert!(headless.child.wait().unwrap().success());
+}
+
+#[test]
+fn peek_is_capped_at_the_end_of_memory() {
+    let mut headless = Headless::spawn();
+
+    let response = headless.request(json!({ "cmd": "peek", "addr": 0x0ffe, "len": u32::MAX }));
+    assert_eq!(response["bytes"].as_array().unwrap().len(), 2);
+
+    assert!(headless
+        .error(r#"{"cmd": "peek", "addr": 4096, "len": 4}"#)
+        .contains("RAM address 0x1000"));
+}
+
+#[test]
+fn errors_keep_the_session_alive() {
+    let mut headless = Headless::spawn();
+
+    // Malformed requests have no id to echo
+    let response = headless.send(r#"{"id": 7, "cmd": "#);
+    assert_eq!(response, json!({ "id": null, "ok": false, "error": response["error"] }));
+    headless.error("[1, 2, 3]");
+
+    // Well formed requests with bad commands keep their id
+    let response = headless.send(r#"{"id": 7, "cmd": "jump"}"#);
+    assert_eq!(response["id"], json!(7));
+    assert!(response["error"].as_str().unwrap().contains("unknown variant"));
+    assert!(headless
+        .error(r#"{"cmd": "step", "cuont": 2}"#)
+        .contains("unknown field"));
+    assert!(headless
+        .error(r#"{"cmd": "press", "key": 16}"#)
+        .contains("key 16 is invalid"));
+    assert!(headless
+        .error(r#"{"cmd": "load", "path": "a.ch8", "rom": [0]}"#)
+        .contains("exactly one"));
+
+    let response = headless.request(json!({ "id": 8, "cmd": "registers" }));
+    assert_eq!(response["id"], json!(8));
+}
+
+#[test]
+fn profile() {
+    let mut headless = Headless::spawn();
+
+    // Main calls 0x206, which calls 0x20c, then main loops forever
+    let rom = [
+        0x22, 0x06, 0x12, 0x02, 0x00, 0x00, 0x60, 0x01, 0x22, 0x0c, 0x00, 0xee, 0x61, 0x02, 0x00, 0xee,
+    ];
+    headless.request(json!({ "cmd": "load", "rom": rom }));
+
+    assert!(headless
+        .error(r#"{"cmd": "profile"}"#)
+        .contains("no profile in progress"));
+
+    headless.request(json!({ "cmd": "profile_start" }));
+    headless.request(json!({ "cmd": "step", "count": 10 }));
+
+    let response = headless.request(json!({ "cmd": "profile" }));
+    assert_eq!(response["total"], json!(10));
+    assert_eq!(
+        response["folded"],
+        json!("main 5\nmain;0x0206 3\nmain;0x0206;0x020c 2\n")
+    );
+    assert!(response["report"].as_str().unwrap().starts_with("# Subroutines\n"));
+
+    // Profiling stops once reported
+    assert!(headless
+        .error(r#"{"cmd": "profile"}"#)
+        .contains("no profile in progress"));
+}
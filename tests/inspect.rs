@@ -0,0 +1,152 @@
+use chip8::{Chip8, Error, Screen, IO};
+
+const ROM: [u8; 6] = [0x6a, 0x42, 0xa3, 0x00, 0x12, 0x04];
+
+struct Headless(Vec<bool>);
+
+impl Screen for Headless {
+    fn get_pixel(&self, x: usize, y: usize) -> bool {
+        self.0[y * 64 + x]
+    }
+
+    fn set_pixel(&mut self, x: usize, y: usize, on: bool) {
+        self.0[y * 64 + x] = on;
+    }
+
+    fn get_width(&self) -> usize {
+        64
+    }
+
+    fn get_height(&self) -> usize {
+        32
+    }
+}
+
+fn load() -> Chip8 {
+    let mut chip8 = Chip8::new(None);
+    chip8.load_rom(&ROM, Some(0x1234)).unwrap();
+    chip8
+}
+
+#[test]
+fn view_after_load() {
+    let chip8 = load();
+    let view = chip8.view();
+
+    assert_eq!(view.get_pc(), 0x0200);
+    assert_eq!(view.get_i(), 0x0000);
+    assert_eq!(view.get_v(), [0x00; 0x10]);
+    assert!(view.get_stack().is_empty());
+    assert_eq!(view.get_dt(), 0);
+    assert_eq!(view.get_st(), 0);
+    assert_eq!(view.get_cycles(), 0);
+
+    let mut rom = [0x00; ROM.len()];
+    view.read(0x0200, &mut rom).unwrap();
+    assert_eq!(rom, ROM);
+
+    let mut font = [0x00; 5];
+    view.read(0x0000, &mut font).unwrap();
+    assert_eq!(font, [0xf0, 0x90, 0x90, 0x90, 0xf0]);
+
+    let err = view.peek(0x1000).unwrap_err();
+    assert!(matches!(err, Error::RamOutOfRange(0x1000)));
+    assert_eq!(err.to_string(), "RAM address 0x1000 is invalid");
+}
+
+#[test]
+fn view_after_first_clock() {
+    let mut chip8 = load();
+    let mut screen = Headless(vec![false; 64 * 32]);
+    let mut audio = false;
+
+    // The first clock executes exactly one instruction
+    chip8
+        .clock(&mut IO {
+            screen: &mut screen,
+            pad: &[false; 0x10],
+            audio: &mut audio,
+        })
+        .unwrap();
+
+    let view = chip8.view();
+    assert_eq!(view.get_v()[0xa], 0x42);
+    assert_eq!(view.get_pc(), 0x0202);
+    assert_eq!(view.get_cycles(), 1);
+}
+
+#[test]
+fn step_single_instructions() {
+    let mut chip8 = load();
+    let mut screen = Headless(vec![false; 64 * 32]);
+    let mut audio = false;
+
+    chip8.edit().unwrap().set_dt(5);
+
+    for _ in 0..2 {
+        chip8
+            .step(&mut IO {
+                screen: &mut screen,
+                pad: &[false; 0x10],
+                audio: &mut audio,
+            })
+            .unwrap();
+    }
+
+    // Timers are left untouched
+    let view = chip8.view();
+    assert_eq!(view.get_i(), 0x0300);
+    assert_eq!(view.get_pc(), 0x0204);
+    assert_eq!(view.get_cycles(), 2);
+    assert_eq!(view.get_dt(), 5);
+}
+
+#[test]
+fn edit_registers() {
+    let mut chip8 = load();
+    let mut editor = chip8.edit().unwrap();
+
+    editor.set_v(0x3, 0x99).unwrap();
+    editor.set_i(0x0300);
+    editor.set_pc(0x0204);
+    editor.set_dt(10);
+    editor.set_st(20);
+    editor.set_rng_state(0xbeef);
+    assert!(matches!(editor.set_v(0x10, 0x00), Err(Error::RegisterOutOfRange(0x10))));
+
+    let view = chip8.view();
+    assert_eq!(view.get_v()[0x3], 0x99);
+    assert_eq!(view.get_i(), 0x0300);
+    assert_eq!(view.get_pc(), 0x0204);
+    assert_eq!(view.get_dt(), 10);
+    assert_eq!(view.get_st(), 20);
+    assert_eq!(view.get_rng_state(), 0xbeef);
+}
+
+#[test]
+fn edit_stack() {
+    let mut chip8 = load();
+    let mut editor = chip8.edit().unwrap();
+
+    editor.set_stack(&[0x0202, 0x0300]).unwrap();
+    assert_eq!(editor.view().get_stack(), [0x0202, 0x0300]);
+
+    assert!(matches!(editor.set_stack(&[0x0200; 0x11]), Err(Error::StackOverflow)));
+    assert_eq!(editor.view().get_stack(), [0x0202, 0x0300]);
+}
+
+#[test]
+fn edit_memory() {
+    let mut chip8 = load();
+    let mut editor = chip8.edit().unwrap();
+
+    editor.write(0x0300, &[0xde, 0xad, 0xbe, 0xef]).unwrap();
+    assert!(matches!(
+        editor.write(0x0ffe, &[0x00; 4]),
+        Err(Error::RamOutOfRange(0x1000))
+    ));
+
+    let mut bytes = [0x00; 4];
+    chip8.view().read(0x0300, &mut bytes).unwrap();
+    assert_eq!(bytes, [0xde, 0xad, 0xbe, 0xef]);
+}
@@ -0,0 +1,206 @@
+use chip8::{Chip8, Error, Framebuffer, Movie, Pcg, WaitMode, IO, STATE_SIZE};
+
+// Counts frames with key 5 held in V1, and draws a random byte in V2 on each loop
+const ROM: [u8; 12] = [0x60, 0x05, 0xe0, 0x9e, 0x12, 0x08, 0x71, 0x01, 0xc2, 0xff, 0x12, 0x00];
+const FRAMES: usize = 120;
+
+struct Machine {
+    chip8: Chip8,
+    screen: Framebuffer,
+}
+
+impl Machine {
+    fn new(rom: &[u8], seed: Option<u16>) -> Self {
+        let mut chip8 = Chip8::new(None);
+        chip8.load_rom(rom, seed).unwrap();
+
+        Self {
+            chip8,
+            screen: Framebuffer::default(),
+        }
+    }
+
+    fn frame(&mut self, key: bool) -> Result<(), Error> {
+        let mut pad = [false; 0x10];
+        pad[0x5] = key;
+
+        self.chip8.frame(&mut IO {
+            screen: &mut self.screen,
+            pad: &pad,
+            audio: &mut false,
+        })
+    }
+
+    fn state(&self) -> Vec<u8> {
+        let mut state = vec![0x00; STATE_SIZE];
+        self.chip8.save_state(&self.screen, &mut state).unwrap();
+        state
+    }
+}
+
+fn record() -> (Movie, Vec<u8>) {
+    let mut machine = Machine::new(&ROM, Some(0x1234));
+    machine.chip8.record_movie().unwrap();
+
+    for frame in 0..FRAMES {
+        machine.frame((30..70).contains(&frame)).unwrap();
+    }
+
+    (machine.chip8.take_movie().unwrap(), machine.state())
+}
+
+#[test]
+fn replay_matches_recording() {
+    let (movie, state) = record();
+    assert_eq!(movie.len(), FRAMES);
+
+    let movie = Movie::from_bytes(&movie.to_bytes()).unwrap();
+
+    // The seed comes from the movie, and live input is ignored
+    let mut machine = Machine::new(&ROM, Some(0x4321));
+    machine.chip8.play_movie(movie).unwrap();
+
+    for _ in 0..FRAMES {
+        machine.frame(true).unwrap();
+    }
+
+    assert_eq!(machine.state(), state);
+    assert!(machine.chip8.view().get_v()[1] > 0);
+
+    // Playback does not fall back to live input past the end
+    assert!(matches!(machine.frame(true), Err(Error::MovieFinished(FRAMES))));
+    assert_eq!(machine.state(), state);
+}
+
+#[test]
+fn desync_is_detected() {
+    let (movie, _) = record();
+
+    let mut machine = Machine::new(&ROM, None);
+    machine.chip8.edit().unwrap().write(0x0300, &[0x01]).unwrap();
+    machine.chip8.play_movie(movie).unwrap();
+
+    assert!(matches!(machine.frame(false), Err(Error::MovieDesync(0))));
+}
+
+#[test]
+fn rom_mismatch() {
+    let (movie, _) = record();
+
+    let mut machine = Machine::new(&ROM[..10], None);
+    assert!(matches!(
+        machine.chip8.play_movie(movie.clone()),
+        Err(Error::MovieRomMismatch(expected, _)) if expected == movie.get_rom_crc()
+    ));
+}
+
+#[test]
+fn rng_mismatch() {
+    let (movie, _) = record();
+    assert_eq!(movie.get_rng(), 0x00);
+
+    let mut machine = Machine::new(&ROM, None);
+    machine.chip8.set_rng(Pcg::new());
+    assert!(matches!(
+        machine.chip8.play_movie(movie),
+        Err(Error::MovieRngMismatch(0x00, 0x01))
+    ));
+
+    // Movies recorded with another generator replay with that generator
+    let mut machine = Machine::new(&ROM, Some(0x1234));
+    machine.chip8.set_rng(Pcg::new());
+    machine.chip8.record_movie().unwrap();
+
+    for _ in 0..FRAMES {
+        machine.frame(false).unwrap();
+    }
+
+    let movie = Movie::from_bytes(&machine.chip8.take_movie().unwrap().to_bytes()).unwrap();
+    assert_eq!(movie.get_rng(), 0x01);
+
+    let state = machine.state();
+    let mut machine = Machine::new(&ROM, None);
+    machine.chip8.set_rng(Pcg::new());
+    machine.chip8.play_movie(movie).unwrap();
+
+    for _ in 0..FRAMES {
+        machine.frame(false).unwrap();
+    }
+
+    assert_eq!(machine.state(), state);
+}
+
+#[test]
+fn wait_mode_is_restored() {
+    // Waits for a key into V0, then counts completed waits in V1
+    let rom = [0xf0, 0x0a, 0x71, 0x01, 0x12, 0x00];
+
+    let mut machine = Machine::new(&rom, None);
+    machine.chip8.set_wait_mode(WaitMode::Release);
+    machine.chip8.record_movie().unwrap();
+
+    for frame in 0..FRAMES {
+        machine.frame((10..20).contains(&frame)).unwrap();
+    }
+
+    let state = machine.state();
+    let movie = Movie::from_bytes(&machine.chip8.take_movie().unwrap().to_bytes()).unwrap();
+    assert_eq!(movie.get_wait_mode(), WaitMode::Release);
+
+    // Replaying in another mode would complete the wait on the press instead of the release
+    let mut machine = Machine::new(&rom, None);
+    machine.chip8.play_movie(movie).unwrap();
+    assert_eq!(machine.chip8.get_wait_mode(), WaitMode::Release);
+
+    for _ in 0..FRAMES {
+        machine.frame(false).unwrap();
+    }
+
+    assert_eq!(machine.state(), state);
+}
+
+#[test]
+fn started_late() {
+    let (movie, _) = record();
+
+    let mut machine = Machine::new(&ROM, None);
+    machine.frame(false).unwrap();
+
+    assert!(matches!(machine.chip8.record_movie(), Err(Error::MovieStartedLate)));
+    assert!(matches!(machine.chip8.play_movie(movie), Err(Error::MovieStartedLate)));
+}
+
+#[test]
+fn edits_are_rejected_during_movies() {
+    let (movie, _) = record();
+
+    let mut machine = Machine::new(&ROM, None);
+    machine.chip8.record_movie().unwrap();
+    machine.frame(false).unwrap();
+    assert!(matches!(machine.chip8.edit(), Err(Error::MovieInProgress)));
+
+    let mut machine = Machine::new(&ROM, None);
+    machine.chip8.play_movie(movie).unwrap();
+    assert!(matches!(machine.chip8.edit(), Err(Error::MovieInProgress)));
+
+    machine.chip8.take_movie().unwrap();
+    machine.chip8.edit().unwrap().set_dt(5);
+    assert_eq!(machine.chip8.view().get_dt(), 5);
+}
+
+#[test]
+fn invalid_files() {
+    let (movie, _) = record();
+    let bytes = movie.to_bytes();
+
+    assert!(matches!(
+        Movie::from_bytes(&bytes[..bytes.len() - 1]),
+        Err(Error::InvalidMovie)
+    ));
+    assert!(matches!(Movie::from_bytes(b"C8MV"), Err(Error::InvalidMovie)));
+
+    // Unknown wait mode
+    let mut bytes = movie.to_bytes();
+    bytes[14] = 0x03;
+    assert!(matches!(Movie::from_bytes(&bytes), Err(Error::InvalidMovie)));
+}
@@ -0,0 +1,139 @@
+use std::net::UdpSocket;
+
+use chip8::{Chip8, Error, Framebuffer, Netplay, IO, STATE_SIZE};
+
+// Counts frames with key 1 held in V1, and frames with key C held in V2
+const ROM: [u8; 18] = [
+    0x60, 0x01, 0xe0, 0x9e, 0x12, 0x08, 0x71, 0x01, 0x60, 0x0c, 0xe0, 0x9e, 0x12, 0x10, 0x72, 0x01, 0x12, 0x00,
+];
+
+struct Peer {
+    chip8: Chip8,
+    netplay: Netplay,
+    screen: Framebuffer,
+    audio: bool,
+}
+
+impl Peer {
+    fn frame(&mut self, key: usize, held: bool) -> Result<bool, Error> {
+        let mut pad = [false; 0x10];
+        pad[key] = held;
+
+        self.netplay.frame(
+            &mut self.chip8,
+            &mut IO {
+                screen: &mut self.screen,
+                pad: &pad,
+                audio: &mut self.audio,
+            },
+        )
+    }
+
+    fn state(&self) -> Vec<u8> {
+        let mut state = vec![0x00; STATE_SIZE];
+        self.chip8.save_state(&self.screen, &mut state).unwrap();
+        state
+    }
+}
+
+fn peers(delay: u32, seeds: (u16, u16)) -> (Peer, Peer) {
+    let sockets = (
+        UdpSocket::bind("127.0.0.1:0").unwrap(),
+        UdpSocket::bind("127.0.0.1:0").unwrap(),
+    );
+    let addrs = (sockets.0.local_addr().unwrap(), sockets.1.local_addr().unwrap());
+
+    let peer = |socket, addr, seed| {
+        let mut chip8 = Chip8::new(None);
+        chip8.load_rom(&ROM, Some(seed)).unwrap();
+
+        Peer {
+            chip8,
+            netplay: Netplay::new(socket, addr, Some(delay)).unwrap(),
+            screen: Framebuffer::default(),
+            audio: false,
+        }
+    };
+
+    (peer(sockets.0, addrs.1, seeds.0), peer(sockets.1, addrs.0, seeds.1))
+}
+
+// Runs both peers until both emulated the given frame count, with the first one holding key 1 and the second
+// one key C, during overlapping ranges of frames
+fn run(a: &mut Peer, b: &mut Peer, frames: u64) -> Result<(), Error> {
+    while a.netplay.get_frame() < frames || b.netplay.get_frame() < frames {
+        let (fa, fb) = (a.netplay.get_frame(), b.netplay.get_frame());
+
+        if fa < frames {
+            a.frame(0x1, (20..50).contains(&fa))?;
+        }
+
+        if fb < frames {
+            b.frame(0xc, (40..90).contains(&fb))?;
+        }
+
+        std::thread::sleep(std::time::Duration::from_micros(200));
+    }
+
+    // Let the last inputs and checksums reach both ends
+    for _ in 0..10 {
+        a.frame(0x1, false)?;
+        b.frame(0xc, false)?;
+        std::thread::sleep(std::time::Duration::from_micros(200));
+    }
+
+    Ok(())
+}
+
+#[test]
+fn peers_stay_in_sync() {
+    let (mut a, mut b) = peers(2, (0x1234, 0x1234));
+    run(&mut a, &mut b, 200).unwrap();
+
+    assert_eq!(a.netplay.get_frame(), b.netplay.get_frame());
+    assert_eq!(a.state(), b.state());
+
+    let v = a.chip8.view().get_v();
+    assert!(v[1] > 0 && v[2] > 0);
+}
+
+#[test]
+fn mispredictions_roll_back() {
+    // Without input delay, remote presses always arrive after the frame they apply to was predicted
+    let (mut a, mut b) = peers(0, (0x1234, 0x1234));
+    run(&mut a, &mut b, 200).unwrap();
+
+    assert!(a.netplay.get_rollbacks() > 0);
+    assert_eq!(a.netplay.get_frame(), b.netplay.get_frame());
+    assert_eq!(a.state(), b.state());
+}
+
+#[test]
+fn desync_is_detected() {
+    let (mut a, mut b) = peers(2, (0x1234, 0x1234));
+    b.chip8.edit().unwrap().set_v(0x1, 0x42).unwrap();
+
+    assert!(matches!(run(&mut a, &mut b, 200), Err(Error::NetplayDesync(0))));
+}
+
+#[test]
+fn config_mismatch_is_detected() {
+    let (mut a, mut b) = peers(2, (0x1234, 0x4321));
+    let (hash_a, hash_b) = (a.chip8.config_hash(), b.chip8.config_hash());
+    assert_ne!(hash_a, hash_b);
+
+    // The second peer receives first, and reports its own hash as local
+    let err = run(&mut a, &mut b, 200).unwrap_err();
+    assert!(matches!(err, Error::NetplayConfigMismatch(local, remote) if (local, remote) == (hash_b, hash_a)));
+}
+
+#[test]
+fn input_delay_is_bounded() {
+    let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+    let addr = socket.local_addr().unwrap();
+
+    assert!(matches!(
+        Netplay::new(socket, addr, Some(100)),
+        Err(Error::InvalidInputDelay(100))
+    ));
+}
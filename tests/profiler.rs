@@ -0,0 +1,76 @@
+use chip8::{Chip8, Framebuffer, Subroutine, IO};
+
+// Main calls 0x206, which calls 0x20c, then main loops forever
+const ROM: [u8; 16] = [
+    0x22, 0x06, 0x12, 0x02, 0x00, 0x00, 0x60, 0x01, 0x22, 0x0c, 0x00, 0xee, 0x61, 0x02, 0x00, 0xee,
+];
+
+fn run(steps: usize) -> chip8::Profiler {
+    let mut chip8 = Chip8::new(None);
+    chip8.load_rom(&ROM, None).unwrap();
+    chip8.record_profile();
+
+    let mut screen = Framebuffer::default();
+    for _ in 0..steps {
+        chip8
+            .step(&mut IO {
+                screen: &mut screen,
+                pad: &[false; 0x10],
+                audio: &mut false,
+            })
+            .unwrap();
+    }
+
+    chip8.take_profile().unwrap()
+}
+
+#[test]
+fn instruction_counts() {
+    let profiler = run(10);
+
+    assert_eq!(profiler.get_total(), 10);
+    assert_eq!(profiler.get_count(0x0200), 1);
+    assert_eq!(profiler.get_count(0x0202), 4);
+    assert_eq!(profiler.get_count(0x020e), 1);
+    assert_eq!(profiler.get_count(0x0204), 0);
+}
+
+#[test]
+fn inclusive_and_exclusive() {
+    let profiler = run(10);
+
+    // Calls and returns are attributed to the caller and callee respectively
+    assert_eq!(
+        profiler.get_subroutines(),
+        [
+            Subroutine {
+                addr: None,
+                exclusive: 5,
+                inclusive: 10,
+            },
+            Subroutine {
+                addr: Some(0x0206),
+                exclusive: 3,
+                inclusive: 5,
+            },
+            Subroutine {
+                addr: Some(0x020c),
+                exclusive: 2,
+                inclusive: 2,
+            },
+        ]
+    );
+}
+
+#[test]
+fn folded_stacks() {
+    let profiler = run(10);
+
+    let mut folded = Vec::new();
+    profiler.write_folded(&mut folded).unwrap();
+
+    assert_eq!(
+        String::from_utf8(folded).unwrap(),
+        "main 5\nmain;0x0206 3\nmain;0x0206;0x020c 2\n"
+    );
+}
@@ -0,0 +1,127 @@
+use std::time::Duration;
+
+use chip8::{Chip8, Framebuffer, Lfsr, ManualTime, Pcg, RandomSource, Scripted, Vip, IO};
+
+// V0 = rand() & 0xff, V1 = rand() & 0x0f, then loop forever
+const ROM: [u8; 6] = [0xc0, 0xff, 0xc1, 0x0f, 0x12, 0x04];
+
+// Stands in for the VIP interpreter, which is not distributed with the crate
+fn interpreter() -> [u8; 0x200] {
+    core::array::from_fn(|i| (i as u8).wrapping_mul(37).wrapping_add(11))
+}
+
+fn run(chip8: &mut Chip8) -> [u8; 2] {
+    let time = ManualTime::new();
+    chip8.set_time_source(time.clone());
+
+    let mut screen = Framebuffer::default();
+    let mut audio = false;
+
+    for _ in 0..2 {
+        chip8
+            .clock(&mut IO {
+                screen: &mut screen,
+                pad: &[false; 0x10],
+                audio: &mut audio,
+            })
+            .unwrap();
+        time.advance(Duration::from_millis(10));
+    }
+
+    let v = chip8.view().get_v();
+    [v[0], v[1]]
+}
+
+#[test]
+fn scripted_sequence() {
+    let mut chip8 = Chip8::new(None);
+    chip8.load_rom(&ROM, None).unwrap();
+    chip8.set_rng(Scripted::new(vec![0xa5, 0x3c]));
+
+    assert_eq!(run(&mut chip8), [0xa5, 0x0c]);
+}
+
+#[test]
+fn pcg_is_deterministic() {
+    let mut a = Pcg::new();
+    let mut b = Pcg::new();
+    a.seed(0x1234);
+    b.seed(0x1234);
+
+    let a: Vec<_> = (0..16).map(|_| a.next_byte()).collect();
+    let b: Vec<_> = (0..16).map(|_| b.next_byte()).collect();
+
+    assert_eq!(a, b);
+    assert!(a.iter().any(|byte| *byte != a[0]));
+}
+
+#[test]
+fn vip_follows_the_interpreter_routine() {
+    let mut vip = Vip::new(&interpreter());
+    vip.seed(0x12fe);
+
+    // Traced by hand through INC R9, GLO R9, PLO RE, GHI R3, PHI RE, GHI R9, SEX RE, ADD, STR R6, SHRC, SEX R6,
+    // ADD, PHI R9, with R3 in page 0x01. The low byte of R9 wraps into its high byte on the second call
+    let bytes: Vec<_> = (0..6).map(|_| vip.next_byte()).collect();
+    assert_eq!(bytes, [0x74, 0xc0, 0x68, 0x1b, 0xdf, 0x3d]);
+    assert_eq!(vip.get_state(), 0x3d04);
+    assert_eq!(vip.get_id(), 0x03);
+
+    // Only the second page of the interpreter is read
+    let mut interpreter = interpreter();
+    interpreter[..0x100].fill(0x00);
+    let mut other = Vip::new(&interpreter);
+    other.seed(0x12fe);
+    assert_eq!((0..6).map(|_| other.next_byte()).collect::<Vec<_>>(), bytes);
+}
+
+#[test]
+fn random_loop_varies() {
+    // Stores 64 random bytes from 0x0300 on, then loops forever
+    const LOOP: [u8; 20] = [
+        0xa3, 0x00, 0x61, 0x40, 0x62, 0x01, 0xc0, 0xff, 0xf0, 0x55, 0xf2, 0x1e, 0x71, 0xff, 0x31, 0x00, 0x12, 0x06,
+        0x12, 0x12,
+    ];
+
+    let generators: [fn(&mut Chip8); 3] = [
+        |chip8| chip8.set_rng(Lfsr::new()),
+        |chip8| chip8.set_rng(Pcg::new()),
+        |chip8| chip8.set_rng(Vip::new(&interpreter())),
+    ];
+
+    for set_rng in generators {
+        let mut chip8 = Chip8::new(None);
+        chip8.load_rom(&LOOP, Some(0x1234)).unwrap();
+        set_rng(&mut chip8);
+
+        let mut screen = Framebuffer::default();
+
+        for _ in 0..400 {
+            chip8
+                .step(&mut IO {
+                    screen: &mut screen,
+                    pad: &[false; 0x10],
+                    audio: &mut false,
+                })
+                .unwrap();
+        }
+
+        let mut bytes = [0x00; 64];
+        chip8.view().read(0x0300, &mut bytes).unwrap();
+
+        let mut distinct = bytes.to_vec();
+        distinct.sort();
+        distinct.dedup();
+        assert!(distinct.len() > 32, "{:02x?}", bytes);
+    }
+}
+
+#[test]
+fn wide_state_is_kept() {
+    let mut chip8 = Chip8::new(None);
+    chip8.load_rom(&ROM, None).unwrap();
+    chip8.set_rng(Pcg::new());
+
+    chip8.edit().unwrap().set_rng_state(0x0123_4567_89ab_cdef);
+    assert_eq!(chip8.view().get_rng_state(), 0x0123_4567_89ab_cdef);
+}
@@ -0,0 +1,79 @@
+use chip8::{Chip8, Framebuffer, SmcEvent, IO};
+
+// Calls 0x20c once, then overwrites its return with a jump to 0x210 and executes it
+const ROM: [u8; 18] = [
+    0x22, 0x0c, 0xa2, 0x0c, 0x60, 0x12, 0x61, 0x10, 0xf1, 0x55, 0x12, 0x0c, 0x00, 0xee, 0x00, 0x00, 0x12, 0x10,
+];
+
+fn run() -> Chip8 {
+    let mut chip8 = Chip8::new(None);
+    chip8.load_rom(&ROM, None).unwrap();
+    chip8.record_smc();
+
+    let mut screen = Framebuffer::default();
+    for _ in 0..10 {
+        chip8
+            .step(&mut IO {
+                screen: &mut screen,
+                pad: &[false; 0x10],
+                audio: &mut false,
+            })
+            .unwrap();
+    }
+
+    chip8
+}
+
+#[test]
+fn rom_patches_executed_code() {
+    let chip8 = run();
+
+    // One event per byte written
+    assert_eq!(chip8.view().get_pc(), 0x0210);
+    assert_eq!(
+        chip8.get_smc().unwrap().get_events(),
+        [
+            SmcEvent {
+                pc: Some(0x0208),
+                addr: 0x020c,
+                old: 0x00ee,
+                new: 0x12ee,
+            },
+            SmcEvent {
+                pc: Some(0x0208),
+                addr: 0x020c,
+                old: 0x12ee,
+                new: 0x1210,
+            },
+        ]
+    );
+}
+
+#[test]
+fn editor_patches_are_reported() {
+    let mut chip8 = run();
+
+    // Unchanged bytes and code never executed are not reported
+    chip8.edit().unwrap().write(0x0210, &[0x12, 0x12]).unwrap();
+    chip8.edit().unwrap().write(0x020e, &[0xff]).unwrap();
+
+    let smc = chip8.take_smc().unwrap();
+    assert_eq!(
+        smc.get_events().last(),
+        Some(&SmcEvent {
+            pc: None,
+            addr: 0x0210,
+            old: 0x1210,
+            new: 0x1212,
+        })
+    );
+
+    let mut report = Vec::new();
+    smc.write_report(&mut report).unwrap();
+    assert_eq!(
+        String::from_utf8(report).unwrap(),
+        "0x0208: instruction at 0x020c modified from 00ee to 12ee\n\
+         0x0208: instruction at 0x020c modified from 12ee to 1210\n\
+         editor: instruction at 0x0210 modified from 1210 to 1212\n"
+    );
+}
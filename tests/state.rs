@@ -0,0 +1,163 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+use chip8::{Bus, Chip8, Error, Framebuffer, Ram, IO, STATE_SIZE};
+
+// Counts up in V0 and stores it at 0x0300 and 0x0f00, then loops
+const ROM: [u8; 12] = [0x70, 0x01, 0xa3, 0x00, 0xf0, 0x55, 0xaf, 0x00, 0xf0, 0x55, 0x12, 0x00];
+
+// Memory whose upper page rejects writes once locked
+#[derive(Default)]
+struct Lockable {
+    ram: Ram,
+    locked: Arc<AtomicBool>,
+}
+
+impl Bus for Lockable {
+    fn read(&mut self, addr: u16) -> Result<u8, Error> {
+        self.ram.read(addr)
+    }
+
+    fn write(&mut self, addr: u16, byte: u8) -> Result<(), Error> {
+        if addr >= 0x0f00 && self.locked.load(Ordering::SeqCst) {
+            return Err(Error::RamOutOfRange(addr));
+        }
+
+        self.ram.write(addr, byte)
+    }
+
+    fn peek(&self, addr: u16) -> Result<u8, Error> {
+        self.ram.peek(addr)
+    }
+}
+
+fn step<B: Bus>(chip8: &mut Chip8<B>, screen: &mut Framebuffer, count: usize) {
+    for _ in 0..count {
+        chip8
+            .step(&mut IO {
+                screen: &mut *screen,
+                pad: &[false; 0x10],
+                audio: &mut false,
+            })
+            .unwrap();
+    }
+}
+
+fn save<B: Bus>(chip8: &Chip8<B>, screen: &Framebuffer) -> Vec<u8> {
+    let mut state = vec![0x00; STATE_SIZE];
+    chip8.save_state(screen, &mut state).unwrap();
+    state
+}
+
+fn load(freq: Option<f32>, rom: &[u8]) -> (Chip8, Framebuffer) {
+    let mut chip8 = Chip8::new(freq);
+    chip8.load_rom(rom, None).unwrap();
+    (chip8, Framebuffer::default())
+}
+
+#[test]
+fn round_trip() {
+    let (mut chip8, mut screen) = load(None, &ROM);
+    step(&mut chip8, &mut screen, 12);
+    let state = save(&chip8, &screen);
+
+    let (mut other, mut other_screen) = load(None, &ROM);
+    other.load_state(&mut other_screen, &state).unwrap();
+    assert_eq!(save(&other, &other_screen), state);
+    assert_eq!(other.view().peek(0x0f00).unwrap(), 0x02);
+}
+
+#[test]
+fn rom_mismatch() {
+    let (mut chip8, mut screen) = load(None, &ROM);
+    step(&mut chip8, &mut screen, 6);
+    let state = save(&chip8, &screen);
+
+    let (mut other, mut other_screen) = load(None, &ROM[..10]);
+    let before = save(&other, &other_screen);
+
+    assert!(matches!(
+        other.load_state(&mut other_screen, &state),
+        Err(Error::StateRomMismatch(_, _))
+    ));
+    assert_eq!(save(&other, &other_screen), before);
+}
+
+#[test]
+fn config_mismatch() {
+    let (chip8, screen) = load(None, &ROM);
+    let state = save(&chip8, &screen);
+
+    let (mut other, mut other_screen) = load(Some(600.0), &ROM);
+    assert!(matches!(
+        other.load_state(&mut other_screen, &state),
+        Err(Error::StateConfigMismatch)
+    ));
+
+    // Frame-locked machines only accept states with the same instructions per frame
+    let (mut chip8, screen) = load(None, &ROM);
+    chip8.set_ipf(10).unwrap();
+    let state = save(&chip8, &screen);
+
+    other.set_ipf(11).unwrap();
+    assert!(matches!(
+        other.load_state(&mut other_screen, &state),
+        Err(Error::StateConfigMismatch)
+    ));
+
+    other.set_ipf(10).unwrap();
+    other.load_state(&mut other_screen, &state).unwrap();
+}
+
+#[test]
+fn invalid_state_is_not_applied() {
+    let (mut chip8, mut screen) = load(None, &ROM);
+    step(&mut chip8, &mut screen, 6);
+    let mut state = save(&chip8, &screen);
+
+    step(&mut chip8, &mut screen, 6);
+    let before = save(&chip8, &screen);
+
+    // Stack pointer past the stack, after the header, registers, I and PC
+    state[5 + 0x10 + 4] = 0x20;
+    assert!(matches!(
+        chip8.load_state(&mut screen, &state),
+        Err(Error::InvalidState)
+    ));
+    assert_eq!(save(&chip8, &screen), before);
+
+    assert!(matches!(
+        chip8.load_state(&mut screen, &state[..STATE_SIZE - 1]),
+        Err(Error::InvalidState)
+    ));
+    assert_eq!(save(&chip8, &screen), before);
+}
+
+#[test]
+fn failed_memory_writes_are_rolled_back() {
+    let bus = Lockable::default();
+    let locked = bus.locked.clone();
+
+    let mut chip8 = Chip8::with_bus(None, bus);
+    chip8.load_rom(&ROM, None).unwrap();
+    let mut screen = Framebuffer::default();
+
+    step(&mut chip8, &mut screen, 6);
+    let state = save(&chip8, &screen);
+
+    step(&mut chip8, &mut screen, 6);
+    let before = save(&chip8, &screen);
+
+    // 0x0300 is written back before 0x0f00 fails, and must be restored
+    locked.store(true, Ordering::SeqCst);
+    assert!(matches!(
+        chip8.load_state(&mut screen, &state),
+        Err(Error::RamOutOfRange(0x0f00))
+    ));
+    assert_eq!(save(&chip8, &screen), before);
+    assert_eq!(chip8.view().peek(0x0300).unwrap(), 0x02);
+
+    locked.store(false, Ordering::SeqCst);
+    chip8.load_state(&mut screen, &state).unwrap();
+    assert_eq!(save(&chip8, &screen), state);
+}
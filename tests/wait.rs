@@ -0,0 +1,205 @@
+use chip8::{Chip8, Error, Framebuffer, KeyEvent, Movie, WaitMode, IO};
+
+// Waits for a key into V0, then loops
+const ROM: [u8; 4] = [0xf0, 0x0a, 0x12, 0x02];
+const KEY: usize = 0x7;
+
+struct Machine {
+    chip8: Chip8,
+    screen: Framebuffer,
+    pad: [bool; 0x10],
+    beep: bool,
+}
+
+impl Machine {
+    fn new(mode: WaitMode) -> Self {
+        let mut chip8 = Chip8::new(None);
+        chip8.load_rom(&ROM, None).unwrap();
+        chip8.set_wait_mode(mode);
+
+        Self {
+            chip8,
+            screen: Framebuffer::default(),
+            pad: [false; 0x10],
+            beep: false,
+        }
+    }
+
+    fn press(&mut self, key: usize) {
+        self.chip8.key_event(KeyEvent::Press(key)).unwrap();
+        self.pad[key] = true;
+    }
+
+    fn release(&mut self, key: usize) {
+        self.chip8.key_event(KeyEvent::Release(key)).unwrap();
+        self.pad[key] = false;
+    }
+
+    fn step(&mut self) {
+        self.chip8
+            .step(&mut IO {
+                screen: &mut self.screen,
+                pad: &self.pad,
+                audio: &mut self.beep,
+            })
+            .unwrap();
+    }
+
+    fn frame(&mut self, pad: &[bool]) -> Result<(), Error> {
+        self.chip8.frame(&mut IO {
+            screen: &mut self.screen,
+            pad,
+            audio: &mut self.beep,
+        })
+    }
+
+    fn is_waiting(&self) -> bool {
+        self.chip8.view().get_pc() == 0x0200
+    }
+}
+
+#[test]
+fn press() {
+    let mut machine = Machine::new(WaitMode::Press);
+
+    // Keys already down when the wait starts are ignored
+    machine.press(0x3);
+    machine.step();
+    machine.step();
+    assert!(machine.is_waiting());
+
+    machine.press(KEY);
+    machine.step();
+    assert!(!machine.is_waiting());
+    assert_eq!(machine.chip8.view().get_v()[0x0], KEY as u8);
+}
+
+#[test]
+fn release() {
+    let mut machine = Machine::new(WaitMode::Release);
+    machine.step();
+
+    machine.press(KEY);
+    machine.step();
+    assert!(machine.is_waiting());
+
+    // Only the key pressed first completes the wait
+    machine.press(0x3);
+    machine.release(0x3);
+    machine.step();
+    assert!(machine.is_waiting());
+
+    machine.release(KEY);
+    machine.step();
+    assert!(!machine.is_waiting());
+    assert_eq!(machine.chip8.view().get_v()[0x0], KEY as u8);
+    assert!(!machine.beep);
+}
+
+#[test]
+fn vip() {
+    let mut machine = Machine::new(WaitMode::Vip);
+    machine.step();
+    assert!(!machine.beep);
+
+    // The buzzer sounds while the key is held
+    machine.press(KEY);
+    machine.step();
+    assert!(machine.is_waiting());
+    assert!(machine.beep);
+
+    machine.release(KEY);
+    machine.step();
+    assert!(!machine.is_waiting());
+    assert_eq!(machine.chip8.view().get_v()[0x0], KEY as u8);
+    assert!(!machine.beep);
+}
+
+#[test]
+fn stale_release_is_ignored() {
+    for mode in [WaitMode::Press, WaitMode::Release, WaitMode::Vip] {
+        let mut machine = Machine::new(mode);
+
+        // Held across the start of the wait, then released and pressed again
+        machine.press(KEY);
+        machine.step();
+        machine.release(KEY);
+        machine.step();
+        assert!(machine.is_waiting());
+
+        machine.press(KEY);
+        machine.step();
+        assert_eq!(machine.is_waiting(), mode != WaitMode::Press, "{:?}", mode);
+
+        machine.release(KEY);
+        machine.step();
+        assert!(!machine.is_waiting(), "{:?}", mode);
+    }
+}
+
+#[test]
+fn taps_between_clocks() {
+    for mode in [WaitMode::Press, WaitMode::Release, WaitMode::Vip] {
+        let mut machine = Machine::new(mode);
+        machine.chip8.set_ipf(10).unwrap();
+        machine.frame(&[false; 0x10]).unwrap();
+
+        // Frames never see the key down, the edges come from events alone
+        machine.press(KEY);
+        machine.release(KEY);
+        machine.frame(&[false; 0x10]).unwrap();
+        assert!(!machine.is_waiting(), "{:?}", mode);
+    }
+}
+
+#[test]
+fn pad_snapshots() {
+    let mut machine = Machine::new(WaitMode::Release);
+    let mut pad = [false; 0x10];
+    machine.frame(&pad).unwrap();
+
+    pad[KEY] = true;
+    machine.frame(&pad).unwrap();
+    assert!(machine.is_waiting());
+
+    pad[KEY] = false;
+    machine.frame(&pad).unwrap();
+    assert!(!machine.is_waiting());
+}
+
+#[test]
+fn invalid_key() {
+    let mut machine = Machine::new(WaitMode::Press);
+
+    let err = machine.chip8.key_event(KeyEvent::Press(0x10)).unwrap_err();
+    assert!(matches!(err, Error::PadOutOfRange(0x10)));
+    assert_eq!(err.to_string(), "Pad key 0x10 is invalid");
+}
+
+#[test]
+fn live_input_is_ignored_during_playback() {
+    let mut machine = Machine::new(WaitMode::Press);
+    machine.chip8.record_movie().unwrap();
+
+    for _ in 0..10 {
+        machine.frame(&[false; 0x10]).unwrap();
+    }
+
+    let movie = machine.chip8.take_movie().unwrap();
+
+    // Neither events nor the live pad may complete the wait, or playback desyncs
+    let mut machine = Machine::new(WaitMode::Press);
+    machine
+        .chip8
+        .play_movie(Movie::from_bytes(&movie.to_bytes()).unwrap())
+        .unwrap();
+    machine.frame(&[false; 0x10]).unwrap();
+
+    for _ in 1..10 {
+        machine.press(KEY);
+        machine.release(KEY);
+        machine.frame(&[true; 0x10]).unwrap();
+    }
+
+    assert!(machine.is_waiting());
+}